@@ -0,0 +1,51 @@
+use crate::errors::ClientResult;
+use crate::{redact, API_KEY_HEADER};
+
+use futures::future::BoxFuture;
+
+// Requests sent by the client are always GETs. Backends should stop reading bodies
+// past max_body_bytes and fail with ResponseTooLarge
+#[derive(Clone)]
+pub struct HttpRequest {
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub max_body_bytes: usize,
+}
+
+impl std::fmt::Debug for HttpRequest {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let headers: Vec<(&str, String)> = self.headers.iter()
+            .map(|(name, value)| if name.eq_ignore_ascii_case(API_KEY_HEADER) {
+                (name.as_str(), redact(value))
+            } else {
+                (name.as_str(), value.clone())
+            })
+            .collect();
+
+        f.debug_struct("HttpRequest")
+            .field("url", &self.url)
+            .field("headers", &headers)
+            .field("max_body_bytes", &self.max_body_bytes)
+            .finish()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct HttpResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl HttpResponse {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.iter()
+            .find(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+// Lets the transport be swapped, typically for a mock returning canned responses in tests
+pub trait HttpBackend: Send + Sync {
+    fn send(&self, request: HttpRequest) -> BoxFuture<'_, ClientResult<HttpResponse>>;
+}
@@ -0,0 +1,68 @@
+use crate::ClientResult;
+use crate::{RateLimit, Response, Service, Shipment, ShipmentQuery, TrackingNumber};
+
+use futures::executor::block_on;
+
+// Synchronous wrapper around the async client, for callers without an async runtime.
+// It drives requests itself, which reqwest can't do without tokio, so lib.rs refuses
+// to build it alongside reqwest-backend
+#[derive(Debug, Clone)]
+pub struct Client {
+    inner: crate::Client,
+}
+
+impl Client {
+    pub fn new(api_key: impl Into<String>) -> Client {
+        Client { inner: crate::Client::new(api_key) }
+    }
+
+    pub fn with_base_url(api_key: impl Into<String>, base_url: &str) -> ClientResult<Client> {
+        Ok(Client { inner: crate::Client::with_base_url(api_key, base_url)? })
+    }
+
+    pub fn rate_limit(&self) -> Option<RateLimit> {
+        self.inner.rate_limit()
+    }
+
+    pub fn track(&self, tracking_number: &str) -> ClientResult<Response> {
+        block_on(self.inner.track(tracking_number))
+    }
+
+    pub fn get_shipments(&self, tracking_number: TrackingNumber) -> ClientResult<Response> {
+        block_on(self.inner.get_shipments(tracking_number))
+    }
+
+    pub fn get_shipments_raw(&self, tracking_number: TrackingNumber) -> ClientResult<(Response, String)> {
+        block_on(self.inner.get_shipments_raw(tracking_number))
+    }
+
+    pub fn get_shipments_with_language(&self, tracking_number: TrackingNumber, language: &str) -> ClientResult<Response> {
+        block_on(self.inner.get_shipments_with_language(tracking_number, language))
+    }
+
+    pub fn get_shipments_for_service(&self, tracking_number: TrackingNumber, service: Service) -> ClientResult<Response> {
+        block_on(self.inner.get_shipments_for_service(tracking_number, service))
+    }
+
+    pub fn get_shipments_with_origin(&self, tracking_number: TrackingNumber, origin_country_code: Option<&str>, recipient_postal_code: Option<&str>) -> ClientResult<Response> {
+        block_on(self.inner.get_shipments_with_origin(tracking_number, origin_country_code, recipient_postal_code))
+    }
+
+    pub fn get_shipments_page(&self, tracking_number: TrackingNumber, offset: u32, limit: u32) -> ClientResult<Response> {
+        block_on(self.inner.get_shipments_page(tracking_number, offset, limit))
+    }
+
+    pub fn get(&self, query: ShipmentQuery) -> ClientResult<Response> {
+        block_on(self.inner.get(query))
+    }
+
+    pub fn get_all_shipments(&self, tracking_number: TrackingNumber) -> ClientResult<Vec<Shipment>> {
+        block_on(self.inner.get_all_shipments(tracking_number))
+    }
+}
+
+impl From<crate::Client> for Client {
+    fn from(inner: crate::Client) -> Client {
+        Client { inner }
+    }
+}
@@ -0,0 +1,59 @@
+use crate::{Client, ClientResult, Response, TrackingNumber};
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+// Keeps the last successful Response per tracking number for ttl, errors are never cached
+pub struct CachingClient {
+    inner: Client,
+    ttl: Duration,
+    entries: Mutex<HashMap<String, (Instant, Response)>>,
+}
+
+impl CachingClient {
+    pub fn new(inner: Client, ttl: Duration) -> CachingClient {
+        CachingClient { inner, ttl, entries: Mutex::new(HashMap::new()) }
+    }
+
+    pub async fn get_shipments(&self, tracking_number: TrackingNumber) -> ClientResult<Response> {
+        if let Some(response) = self.cached(&tracking_number) { return Ok(response) }
+
+        let response = self.inner.get_shipments(tracking_number.clone()).await?;
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.insert(tracking_number.as_str().to_string(), (Instant::now(), response.clone()));
+        }
+
+        Ok(response)
+    }
+
+    pub fn invalidate(&self, tracking_number: &TrackingNumber) {
+        if let Ok(mut entries) = self.entries.lock() { entries.remove(tracking_number.as_str()); }
+    }
+
+    pub fn clear(&self) {
+        if let Ok(mut entries) = self.entries.lock() { entries.clear() }
+    }
+
+    pub fn inner(&self) -> &Client {
+        &self.inner
+    }
+
+    fn cached(&self, tracking_number: &TrackingNumber) -> Option<Response> {
+        let mut entries = self.entries.lock().ok()?;
+        let (fetched_at, response) = entries.get(tracking_number.as_str())?;
+        if fetched_at.elapsed() < self.ttl { return Some(response.clone()) }
+
+        entries.remove(tracking_number.as_str());
+        None
+    }
+}
+
+impl std::fmt::Debug for CachingClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("CachingClient")
+            .field("inner", &self.inner)
+            .field("ttl", &self.ttl)
+            .finish()
+    }
+}
@@ -1,34 +1,145 @@
+use serde::{Deserialize, Serialize};
 use std::error::Error;
+use std::time::Duration;
 
 pub type ClientResult<T> = std::result::Result<T, ClientError>;
 
 #[derive(Debug)]
 pub enum ClientError {
-    ParcelNotFound,
-    Unauthorized,
+    ParcelNotFound { tracking_number: String, api_error: Option<DhlApiError> },
+    Unauthorized { api_error: Option<DhlApiError> },
+    // No longer returned, Transport and InvalidResponse tell the two cases apart
     ServerError,
+    InvalidBaseUrl,
+    MissingApiKey,
+    Timeout,
+    InvalidLanguage,
+    UnexpectedStatus { status: u16, body: String, api_error: Option<DhlApiError> },
+    MissingEnvVar { name: String },
+    DocumentNotAvailable,
+    NoShipments,
+    InvalidCountryCode,
+    RateLimited { retry_after: Option<Duration> },
+    InvalidLimit { limit: u32, max: u32 },
+    // Connection, DNS, TLS or IO failures, as opposed to a response that couldn't be parsed
+    Transport { message: String },
+    InvalidResponse { message: String },
+    Cancelled,
+    ResponseTooLarge { limit: usize },
+    InvalidTrackingNumber { tracking_number: String, reason: TrackingNumberError },
+    InvalidHeader { name: String },
 }
 
 impl std::fmt::Display for ClientError {
     fn fmt(&self, f: &mut ::std::fmt::Formatter) -> Result<(), ::std::fmt::Error> {
         match *self {
-            ClientError::ParcelNotFound => f.write_str("Parcel number could not be found"),
-            ClientError::Unauthorized => f.write_str("Unauthorized, check your API key"),
+            ClientError::ParcelNotFound { ref tracking_number, api_error: Some(ref api_error) } => write!(f, "Parcel number {} could not be found: {}", tracking_number, api_error),
+            ClientError::ParcelNotFound { ref tracking_number, api_error: None } => write!(f, "Parcel number {} could not be found", tracking_number),
+            ClientError::Unauthorized { api_error: Some(ref api_error) } => write!(f, "Unauthorized, check your API key: {}", api_error),
+            ClientError::Unauthorized { api_error: None } => f.write_str("Unauthorized, check your API key"),
             ClientError::ServerError => f.write_str("Could not reach server or parse response"),
+            ClientError::InvalidBaseUrl => f.write_str("Base URL is not a valid absolute URL"),
+            ClientError::MissingApiKey => f.write_str("No API key was provided, or it is empty"),
+            ClientError::Timeout => f.write_str("Request to the server timed out"),
+            ClientError::InvalidLanguage => f.write_str("Language must be a two-letter code"),
+            ClientError::UnexpectedStatus { status, api_error: Some(ref api_error), .. } => write!(f, "Server answered with status {}: {}", status, api_error),
+            ClientError::UnexpectedStatus { status, ref body, .. } => write!(f, "Server answered with status {}: {}", status, body),
+            ClientError::MissingEnvVar { ref name } => write!(f, "Environment variable {} is missing or empty", name),
+            ClientError::DocumentNotAvailable => f.write_str("Document is not available yet"),
+            ClientError::NoShipments => f.write_str("No shipment data is available yet for this parcel"),
+            ClientError::InvalidCountryCode => f.write_str("Country code must be two uppercase letters"),
+            ClientError::RateLimited { retry_after: Some(retry_after) } => write!(f, "Rate limited by the server, retry in {}s", retry_after.as_secs()),
+            ClientError::RateLimited { retry_after: None } => f.write_str("Rate limited by the server"),
+            ClientError::InvalidLimit { limit, max } => write!(f, "Limit must be between 1 and {}, got {}", max, limit),
+            ClientError::Transport { ref message } => write!(f, "Could not reach server: {}", message),
+            ClientError::InvalidResponse { ref message } => write!(f, "Could not parse server response: {}", message),
+            ClientError::Cancelled => f.write_str("Request was cancelled"),
+            ClientError::ResponseTooLarge { limit } => write!(f, "Response body is larger than {} bytes", limit),
+            ClientError::InvalidTrackingNumber { ref tracking_number, ref reason } => write!(f, "{}: {}", reason, tracking_number),
+            ClientError::InvalidHeader { ref name } => write!(f, "Header {:?} has an invalid name or value", name),
         }
     }
 }
 
 impl Error for ClientError {}
 
+// The problem details DHL sends along with error statuses
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DhlApiError {
+    pub status: Option<u16>,
+    pub title: Option<String>,
+    pub detail: Option<String>,
+}
+
+impl std::fmt::Display for DhlApiError {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> Result<(), ::std::fmt::Error> {
+        match (&self.title, &self.detail) {
+            (Some(title), Some(detail)) => write!(f, "{}: {}", title, detail),
+            (Some(message), None) | (None, Some(message)) => f.write_str(message),
+            (None, None) => f.write_str("No details given"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TrackingNumberError {
+    Empty,
+    TooShort,
+    UnrecognizedFormat,
+    InvalidCheckDigit,
+}
+
+impl std::fmt::Display for TrackingNumberError {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> Result<(), ::std::fmt::Error> {
+        match *self {
+            TrackingNumberError::Empty => f.write_str("Tracking Number is empty"),
+            TrackingNumberError::TooShort => f.write_str("Tracking Number is too short for any DHL format"),
+            TrackingNumberError::UnrecognizedFormat => f.write_str("Tracking Number did not match DHL format"),
+            TrackingNumberError::InvalidCheckDigit => f.write_str("Tracking Number check digit is invalid"),
+        }
+    }
+}
+
+impl Error for TrackingNumberError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseValueError {
+    pub message: &'static str,
+    pub value: String,
+}
+
+impl std::fmt::Display for ParseValueError {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> Result<(), ::std::fmt::Error> {
+        write!(f, "{}: {}", self.message, self.value)
+    }
+}
+
+impl Error for ParseValueError {}
+
+#[cfg(feature = "surf-backend")]
 impl From<surf::Exception> for ClientError {
-    fn from(_err: surf::Exception) -> Self {
-        ClientError::ServerError
+    fn from(err: surf::Exception) -> Self {
+        ClientError::Transport { message: err.to_string() }
+    }
+}
+
+#[cfg(feature = "reqwest-backend")]
+impl From<reqwest::Error> for ClientError {
+    fn from(err: reqwest::Error) -> Self {
+        if err.is_decode() { return ClientError::InvalidResponse { message: err.to_string() } }
+
+        ClientError::Transport { message: err.to_string() }
     }
 }
 
 impl From<std::io::Error> for ClientError {
-    fn from(_err: std::io::Error) -> Self {
-        ClientError::ServerError
+    fn from(err: std::io::Error) -> Self {
+        ClientError::Transport { message: err.to_string() }
+    }
+}
+
+impl From<serde_json::Error> for ClientError {
+    fn from(err: serde_json::Error) -> Self {
+        ClientError::InvalidResponse { message: err.to_string() }
     }
 }
@@ -0,0 +1,48 @@
+use crate::errors::ClientError::*;
+use crate::{AttemptError, Client, ClientError, ClientResult, HttpRequest, HttpResponse, Response, ShipmentQuery, TrackingNumber};
+
+use std::convert::TryFrom;
+
+// Lets the client run on top of a transport that already speaks the http crate types,
+// the status and body handling stays the same as for the built-in backends
+impl Client {
+    pub fn http_request(&self, query: &ShipmentQuery) -> ClientResult<http::Request<()>> {
+        let uri = self.query_uri(query)?;
+        http::Request::try_from(self.request(&uri))
+    }
+
+    pub fn parse_http_response(&self, tracking_number: &TrackingNumber, response: http::Response<Vec<u8>>) -> ClientResult<Response> {
+        let (response, _) = self.handle_response(tracking_number, HttpResponse::from(response))
+            .map_err(|err| match err {
+                AttemptError::Transient { error, .. } | AttemptError::Fatal(error) => error,
+            })?;
+        if response.shipments.is_empty() { return Err(NoShipments) }
+
+        Ok(response)
+    }
+}
+
+impl TryFrom<HttpRequest> for http::Request<()> {
+    type Error = ClientError;
+
+    fn try_from(request: HttpRequest) -> ClientResult<http::Request<()>> {
+        let mut builder = http::Request::get(request.url);
+        for (name, value) in request.headers {
+            builder = builder.header(name, value);
+        }
+
+        builder.body(()).map_err(|err| Transport { message: err.to_string() })
+    }
+}
+
+// Headers that aren't valid UTF-8 are dropped, DHL only sends ASCII ones
+impl From<http::Response<Vec<u8>>> for HttpResponse {
+    fn from(response: http::Response<Vec<u8>>) -> HttpResponse {
+        let (parts, body) = response.into_parts();
+        let headers = parts.headers.iter()
+            .filter_map(|(name, value)| Some((name.as_str().to_string(), value.to_str().ok()?.to_string())))
+            .collect();
+
+        HttpResponse { status: parts.status.as_u16(), headers, body }
+    }
+}
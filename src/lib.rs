@@ -1,16 +1,64 @@
 use crate::errors::ClientError::*;
-use crate::errors::ClientResult;
 
-use chrono::{DateTime,NaiveDateTime};
+use chrono::{DateTime,NaiveDate,NaiveDateTime};
 use chrono::offset::Utc;
+use futures::future::{self, AbortRegistration, Abortable, Either};
+use futures::stream::{self, Stream, StreamExt};
+use futures_timer::Delay;
 use regex::Regex;
 use serde::{Serialize, Deserialize, de};
+use std::borrow::Cow;
+use std::collections::{HashSet, VecDeque};
 use std::convert::TryFrom;
-use surf::http;
+use std::future::Future;
+use std::marker::PhantomData;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use url::{form_urlencoded, Url};
 
+#[cfg(all(feature = "surf-backend", feature = "reqwest-backend"))]
+compile_error!("surf-backend and reqwest-backend are mutually exclusive, disable default features to use reqwest-backend");
+
+#[cfg(not(any(feature = "surf-backend", feature = "reqwest-backend")))]
+compile_error!("Either the surf-backend or the reqwest-backend feature must be enabled");
+
+#[cfg(all(feature = "blocking", feature = "reqwest-backend"))]
+compile_error!("blocking drives requests without a Tokio runtime, which reqwest-backend needs, so it requires surf-backend");
+
+// Forwards to tracing when the feature is enabled, and compiles to nothing otherwise
+macro_rules! trace_event {
+    ($level:ident, $($arg:tt)+) => {
+        #[cfg(feature = "tracing")]
+        tracing::$level!($($arg)+);
+    };
+}
+
+mod backend;
+#[cfg(feature = "cache")]
+mod cache;
 mod errors;
+#[cfg(feature = "http")]
+mod http_types;
+#[cfg(feature = "reqwest-backend")]
+mod reqwest_backend;
+#[cfg(feature = "surf-backend")]
+mod surf_backend;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+pub use crate::backend::{HttpBackend, HttpRequest, HttpResponse};
+#[cfg(feature = "cache")]
+pub use crate::cache::CachingClient;
+#[cfg(feature = "reqwest-backend")]
+pub use crate::reqwest_backend::ReqwestBackend;
+#[cfg(feature = "surf-backend")]
+pub use crate::surf_backend::SurfBackend;
+pub use crate::errors::{ClientError, ClientResult, DhlApiError, ParseValueError, TrackingNumberError};
+
+#[cfg(feature = "blocking")]
+pub mod blocking;
+
+// Serializes to the strings DHL uses, like "parcel-de", so values round-trip
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Service {
     Freight,
     Express,
@@ -31,6 +79,7 @@ impl TryFrom<&str> for Service {
             "express" => Ok(Service::Express),
             "parcel-de" => Ok(Service::ParcelDE),
             "parcel-nl" => Ok(Service::ParcelNL),
+            "parcel-pl" => Ok(Service::ParcelPL),
             "dsc" => Ok(Service::DSC),
             "dgf" => Ok(Service::DGF),
             "ecommerce" => Ok(Service::Ecommerce),
@@ -39,7 +88,61 @@ impl TryFrom<&str> for Service {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl FromStr for Service {
+    type Err = ParseValueError;
+
+    fn from_str(service: &str) -> Result<Self, Self::Err> {
+        Service::try_from(service).map_err(|message| ParseValueError { message, value: service.to_string() })
+    }
+}
+
+impl Service {
+    pub const ALL: [Service; 8] = [
+        Service::Freight,
+        Service::Express,
+        Service::ParcelDE,
+        Service::ParcelNL,
+        Service::ParcelPL,
+        Service::DSC,
+        Service::DGF,
+        Service::Ecommerce,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Service::Freight => "freight",
+            Service::Express => "express",
+            Service::ParcelDE => "parcel-de",
+            Service::ParcelNL => "parcel-nl",
+            Service::ParcelPL => "parcel-pl",
+            Service::DSC => "dsc",
+            Service::DGF => "dgf",
+            Service::Ecommerce => "ecommerce",
+        }
+    }
+}
+
+impl std::fmt::Display for Service {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Serialize for Service {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for Service {
+    fn deserialize<D: de::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let service = String::deserialize(deserializer)?;
+
+        Service::try_from(service.as_ref()).map_err(de::Error::custom)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum StatusCode {
     PreTransit,
     Transit,
@@ -63,13 +166,86 @@ impl TryFrom<&str> for StatusCode {
     }
 }
 
-#[derive(Clone, Serialize, Deserialize)]
+impl FromStr for StatusCode {
+    type Err = ParseValueError;
+
+    fn from_str(status_code: &str) -> Result<Self, Self::Err> {
+        StatusCode::try_from(status_code).map_err(|message| ParseValueError { message, value: status_code.to_string() })
+    }
+}
+
+impl StatusCode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StatusCode::PreTransit => "pre-transit",
+            StatusCode::Transit => "transit",
+            StatusCode::Delivered => "delivered",
+            StatusCode::Failure => "failure",
+            StatusCode::Unknown => "unknown",
+        }
+    }
+
+    pub fn is_terminal(&self) -> bool {
+        matches!(self, StatusCode::Delivered | StatusCode::Failure)
+    }
+
+    // PreTransit < Transit < Delivered. Unknown tells nothing about progress so it ranks lowest,
+    // and Failure ranks with Transit since parcels fail somewhere on their way
+    pub fn progress_rank(&self) -> u8 {
+        match self {
+            StatusCode::Unknown => 0,
+            StatusCode::PreTransit => 1,
+            StatusCode::Transit | StatusCode::Failure => 2,
+            StatusCode::Delivered => 3,
+        }
+    }
+}
+
+impl std::fmt::Display for StatusCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Serialize for StatusCode {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for StatusCode {
+    fn deserialize<D: de::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let status_code = String::deserialize(deserializer)?;
+
+        StatusCode::try_from(status_code.as_ref()).map_err(de::Error::custom)
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Place {
     pub address: Address,
+    pub service_point: Option<ServicePoint>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+}
+
+impl Place {
+    pub fn coordinates(&self) -> Option<(f64, f64)> {
+        Some((self.latitude?, self.longitude?))
+    }
+}
+
+// DHL service point or locker where a parcel awaits pickup
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServicePoint {
+    pub id: Option<String>,
+    pub name: Option<String>,
+    pub url: Option<String>,
 }
 
-#[derive(Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Address {
     pub country_code: Option<String>,
@@ -78,47 +254,175 @@ pub struct Address {
     pub street_address: Option<String>,
 }
 
-#[derive(Clone, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
+// Formats as "Main St 1, 12345 Berlin, DE", leaving out whatever is missing
+impl std::fmt::Display for Address {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let city = join_present(&[&self.postal_code, &self.address_locality], " ");
+        let parts = [self.street_address.clone(), Some(city), self.country_code.clone()];
+
+        f.write_str(&join_present(&parts.iter().collect::<Vec<_>>(), ", "))
+    }
+}
+
+fn csv_field(field: &str) -> String {
+    if !field.contains(&[',', '"', '\n', '\r'][..]) { return field.to_string() }
+
+    format!("\"{}\"", field.replace('"', "\"\""))
+}
+
+fn join_present(parts: &[&Option<String>], separator: &str) -> String {
+    parts.iter()
+        .filter_map(|part| part.as_deref().map(str::trim))
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<&str>>()
+        .join(separator)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", from = "ShipmentEventFields")]
 pub struct ShipmentEvent {
-    #[serde(deserialize_with = "deserialize_dhl_datetime")]
     pub timestamp: DateTime<Utc>,
     pub location: Option<Place>,
-    #[serde(deserialize_with = "deserialize_status_code")]
-    #[serde(default)] // this fellow allows serde not to panick if the field is missing
     pub status_code: Option<StatusCode>,
     pub description: Option<String>,
     pub remark: Option<String>,
     pub next_steps: Option<String>,
+    // DHL's own label for the status, like "DELIVERED", sent by most services
+    pub status: Option<String>,
+    // Finer grained code, mostly sent by the parcel services
+    pub status_code_detail: Option<String>,
+    // Pieces the event applies to, sent by express and freight for multi-piece shipments
+    pub piece_ids: Vec<String>,
+    // The code DHL sent when it isn't a known one and status_code fell back to Unknown
+    pub raw_status: Option<String>,
+}
+
+// ShipmentEvent as DHL sends it, with the status code left raw so unknown ones can be kept
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ShipmentEventFields {
+    #[serde(deserialize_with = "deserialize_dhl_datetime")]
+    timestamp: DateTime<Utc>,
+    location: Option<Place>,
+    #[serde(default)] // this fellow allows serde not to panick if the field is missing
+    status_code: Option<RawStatusCode>,
+    description: Option<String>,
+    remark: Option<String>,
+    next_steps: Option<String>,
+    status: Option<String>,
+    status_code_detail: Option<String>,
+    #[serde(default)]
+    piece_ids: Vec<String>,
+    raw_status: Option<String>,
+}
+
+impl From<ShipmentEventFields> for ShipmentEvent {
+    fn from(fields: ShipmentEventFields) -> Self {
+        let (status_code, raw_status) = parse_status_code(fields.status_code);
+        // A rawStatus read back from a serialized event only counts while the code is still unknown
+        let raw_status = raw_status.or(fields.raw_status.filter(|_| status_code == Some(StatusCode::Unknown)));
+
+        ShipmentEvent {
+            timestamp: fields.timestamp,
+            location: fields.location,
+            status_code,
+            description: fields.description,
+            remark: fields.remark,
+            next_steps: fields.next_steps,
+            status: fields.status,
+            status_code_detail: fields.status_code_detail,
+            piece_ids: fields.piece_ids,
+            raw_status,
+        }
+    }
 }
 
-#[derive(Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ShipmentDetails {
     pub carrier: Option<Organization>,
     pub product: Option<Product>,
     pub receiver: Option<Person>,
     pub sender: Option<Person>,
-    pub proof_of_delivery: ProofOfDelivery,
+    pub proof_of_delivery: Option<ProofOfDelivery>,
+    #[serde(default)]
     pub total_number_of_pieces: u32,
+    #[serde(default)]
     pub piece_ids: Vec<String>,
+    #[serde(deserialize_with = "deserialize_null_as_default")]
+    #[serde(default)]
+    pub references: Vec<Reference>,
+    // Per-piece tracking, only sent for multi-piece shipments
+    #[serde(deserialize_with = "deserialize_null_as_default")]
+    #[serde(default)]
+    pub pieces: Vec<PieceStatus>,
+    // DHL sends weight and dimensions right inside the details
+    #[serde(flatten, deserialize_with = "deserialize_physical_attributes")]
+    pub physical_attributes: Option<PhysicalAttributes>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PieceStatus {
+    pub id: String,
+    pub status: Option<ShipmentEvent>,
+    #[serde(default)]
+    pub events: Vec<ShipmentEvent>,
+}
+
+impl PieceStatus {
+    // The piece's status when DHL sends one, otherwise its most recent event
+    pub fn latest_event(&self) -> Option<&ShipmentEvent> {
+        self.status.as_ref().or_else(|| self.events.iter().max_by_key(|event| event.timestamp))
+    }
+}
+
+// Customer references and order numbers the shipper attached to the shipment
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Reference {
+    pub number: String,
+    #[serde(rename = "type")]
+    pub reference_type: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PhysicalAttributes {
+    pub weight: Option<Measurement>,
+    pub dimensions: Option<Dimensions>,
 }
 
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Dimensions {
+    pub length: Option<Measurement>,
+    pub width: Option<Measurement>,
+    pub height: Option<Measurement>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Measurement {
+    pub value: f64,
+    pub unit_text: Option<String>,
+}
 
-#[derive(Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Product {
-    pub description: String,
-    pub product_name: String,
+    pub description: Option<String>,
+    pub product_name: Option<String>,
 }
 
-#[derive(Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ProofOfDelivery {
     pub document_url: String,
+    pub signed_by: Option<String>,
+    pub signature_url: Option<String>,
 }
 
-#[derive(Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Person {
     pub family_name: String,
@@ -126,55 +430,357 @@ pub struct Person {
     pub name: String,
 }
 
-#[derive(Clone, Serialize, Deserialize)]
+impl Person {
+    // Falls back on given and family names when DHL leaves the full name empty
+    pub fn display_name(&self) -> String {
+        if !self.name.trim().is_empty() { return self.name.trim().to_string() }
+
+        [self.given_name.trim(), self.family_name.trim()].iter()
+            .filter(|part| !part.is_empty())
+            .copied()
+            .collect::<Vec<&str>>()
+            .join(" ")
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Organization {
-    pub description: String,
-    pub organization_name: String,
+    pub description: Option<String>,
+    pub organization_name: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeliveryEstimate {
+    Exact(DateTime<Utc>),
+    Window { from: DateTime<Utc>, to: DateTime<Utc> },
+}
+
+impl DeliveryEstimate {
+    // Collapses the estimate to a single point in time, the start of the window if there is one
+    pub fn datetime(&self) -> DateTime<Utc> {
+        match *self {
+            DeliveryEstimate::Exact(datetime) => datetime,
+            DeliveryEstimate::Window { from, .. } => from,
+        }
+    }
+
+    // The latest the parcel is expected, the end of the window if there is one
+    pub fn deadline(&self) -> DateTime<Utc> {
+        match *self {
+            DeliveryEstimate::Exact(datetime) => datetime,
+            DeliveryEstimate::Window { to, .. } => to,
+        }
+    }
+}
+
+// The shapes DHL sends, a date or datetime on its own or a window with both ends
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum RawEstimate {
+    Single(String),
+    #[serde(rename_all = "camelCase")]
+    Window { estimated_from: String, estimated_through: String },
+}
+
+// Written back in DHL's own shapes so that serialized shipments parse again
+impl Serialize for DeliveryEstimate {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let raw = match *self {
+            DeliveryEstimate::Exact(datetime) => RawEstimate::Single(datetime.to_rfc3339()),
+            DeliveryEstimate::Window { from, to } => RawEstimate::Window { estimated_from: from.to_rfc3339(), estimated_through: to.to_rfc3339() },
+        };
+
+        raw.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for DeliveryEstimate {
+    fn deserialize<D: de::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let estimate = match RawEstimate::deserialize(deserializer)? {
+            RawEstimate::Single(date_str) => parse_dhl_datetime(&date_str)
+                .map(DeliveryEstimate::Exact)
+                .or_else(|| parse_dhl_day(&date_str)),
+            RawEstimate::Window { estimated_from, estimated_through } => parse_dhl_datetime(&estimated_from)
+                .zip(parse_dhl_datetime(&estimated_through))
+                .map(|(from, to)| DeliveryEstimate::Window { from, to }),
+        };
+
+        estimate.ok_or_else(|| de::Error::custom("Could not parse date"))
+    }
 }
 
-#[derive(Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Shipment {
     pub id: String,
-    #[serde(deserialize_with = "deserialize_service")]
     pub service: Service,
     pub origin: Option<Place>,
     pub destination: Option<Place>,
     pub status: ShipmentEvent,
-    #[serde(deserialize_with = "deserialize_dhl_date")]
-    pub estimated_time_of_delivery: DateTime<Utc>,
+    // Missing or null until DHL has an estimate
+    #[serde(default)]
+    pub estimated_time_of_delivery: Option<DeliveryEstimate>,
     pub estimated_time_of_delivery_remark: Option<String>,
     pub details: ShipmentDetails,
     pub events: Vec<ShipmentEvent>,
+    // When DHL last updated its record of the shipment, not sent by every service
+    #[serde(deserialize_with = "deserialize_optional_dhl_datetime")]
+    #[serde(default)]
+    pub last_updated: Option<DateTime<Utc>>,
 }
 
-#[derive(Clone, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
+impl Shipment {
+    pub fn latest_event(&self) -> Option<&ShipmentEvent> {
+        self.events.iter().max_by_key(|event| event.timestamp)
+    }
+
+    // The sort is stable, so events sharing a timestamp keep their original order
+    pub fn events_sorted(&self) -> Vec<&ShipmentEvent> {
+        let mut events: Vec<&ShipmentEvent> = self.events.iter().collect();
+        events.sort_by_key(|event| event.timestamp);
+        events
+    }
+
+    // DHL usually repeats the status as the newest entry of events, but not always,
+    // so this isn't guaranteed to show up in events. See history for a merged list
+    pub fn current_status(&self) -> &ShipmentEvent {
+        &self.status
+    }
+
+    // events_sorted with status added unless an event already has its timestamp and description
+    pub fn history(&self) -> Vec<&ShipmentEvent> {
+        let mut events = self.events_sorted();
+        let is_listed = events.iter()
+            .any(|event| event.timestamp == self.status.timestamp && event.description == self.status.description);
+        if !is_listed {
+            let position = events.iter().position(|event| event.timestamp > self.status.timestamp).unwrap_or(events.len());
+            events.insert(position, &self.status);
+        }
+
+        events
+    }
+
+    // Consuming counterpart of events_sorted, oldest first
+    pub fn into_events(self) -> impl Iterator<Item = ShipmentEvent> {
+        let mut events = self.events;
+        events.sort_by_key(|event| event.timestamp);
+        events.into_iter()
+    }
+
+    // Strictly newer than since, oldest first
+    pub fn events_since(&self, since: DateTime<Utc>) -> Vec<&ShipmentEvent> {
+        self.events_sorted().into_iter().filter(|event| event.timestamp > since).collect()
+    }
+
+    // One row per event, oldest first, with the fields DHL left out blank
+    pub fn events_to_csv(&self) -> String {
+        let mut csv = String::from("timestamp,status,location,description\n");
+        for event in self.events_sorted() {
+            let status = event.status_code.as_ref().map(StatusCode::as_str).or(event.status.as_deref()).unwrap_or_default();
+            let location = event.location.as_ref().map(|place| place.address.to_string()).unwrap_or_default();
+            let fields = [event.timestamp.to_rfc3339(), status.to_string(), location, event.description.clone().unwrap_or_default()];
+
+            csv.push_str(&fields.iter().map(|field| csv_field(field)).collect::<Vec<String>>().join(","));
+            csv.push('\n');
+        }
+        csv
+    }
+
+    pub fn events_with_status(&self, code: &StatusCode) -> Vec<&ShipmentEvent> {
+        self.events.iter().filter(|event| event.status_code.as_ref() == Some(code)).collect()
+    }
+
+    pub fn is_delivered(&self) -> bool {
+        matches!(self.status.status_code, Some(StatusCode::Delivered))
+    }
+
+    pub fn origin_country(&self) -> Option<&str> {
+        self.origin.as_ref()?.address.country_code.as_deref()
+    }
+
+    pub fn destination_country(&self) -> Option<&str> {
+        self.destination.as_ref()?.address.country_code.as_deref()
+    }
+
+    // Falls back on the piece ids for services that leave totalNumberOfPieces out
+    pub fn piece_count(&self) -> u32 {
+        match self.details.total_number_of_pieces {
+            0 => self.details.piece_ids.len() as u32,
+            total => total,
+        }
+    }
+
+    pub fn piece_ids(&self) -> &[String] {
+        &self.details.piece_ids
+    }
+
+    // Only past the whole estimated window counts as delayed
+    pub fn is_delayed(&self, now: DateTime<Utc>) -> bool {
+        !self.is_delivered() && self.estimated_time_of_delivery.as_ref().is_some_and(|estimate| estimate.deadline() < now)
+    }
+
+    // From the earliest event to delivery, None until the shipment is delivered
+    pub fn transit_duration(&self) -> Option<chrono::Duration> {
+        let delivered_at = self.events_with_status(&StatusCode::Delivered).into_iter()
+            .map(|event| event.timestamp)
+            .min()
+            .or_else(|| Some(self.status.timestamp).filter(|_| self.is_delivered()))?;
+        let started_at = self.events.iter().map(|event| event.timestamp).min()?;
+
+        Some(delivered_at - started_at.min(delivered_at))
+    }
+
+    // Taken from the latest delivered event that has them, the current status included
+    pub fn delivery_coordinates(&self) -> Option<(f64, f64)> {
+        self.events_with_status(&StatusCode::Delivered).into_iter()
+            .chain(Some(&self.status).filter(|status| status.status_code == Some(StatusCode::Delivered)))
+            .filter_map(|event| Some((event.timestamp, event.location.as_ref()?.coordinates()?)))
+            .max_by_key(|(timestamp, _)| *timestamp)
+            .map(|(_, coordinates)| coordinates)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", from = "ResponseRoot<Shipment>")]
 pub struct Response {
     pub shipments: Vec<Shipment>,
     pub possible_additional_shipments_url: Vec<String>,
 }
 
-fn deserialize_service<'de ,D>(deserializer: D) -> Result<Service, D::Error>
+impl Response {
+    pub fn shipment_by_id(&self, id: &str) -> Option<&Shipment> {
+        self.shipments.iter().find(|shipment| shipment.id == id)
+    }
+
+    pub fn ids(&self) -> Vec<&str> {
+        self.shipments.iter().map(|shipment| shipment.id.as_str()).collect()
+    }
+}
+
+pub fn parse_response(json: &str) -> ClientResult<Response> {
+    Ok(serde_json::from_str(json)?)
+}
+
+#[derive(Debug, Clone)]
+pub struct ShipmentParseError {
+    pub index: usize,
+    pub message: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct TolerantResponse {
+    pub response: Response,
+    pub errors: Vec<ShipmentParseError>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ResponseFields<T> {
+    shipments: Vec<T>,
+    #[serde(deserialize_with = "deserialize_null_as_default")]
+    #[serde(default)]
+    possible_additional_shipments_url: Vec<String>,
+}
+
+// Some gateways in front of DHL send the shipments array as the root instead of the usual object
+struct ResponseRoot<T>(ResponseFields<T>);
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for ResponseRoot<T> {
+    fn deserialize<D: de::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct RootVisitor<T>(PhantomData<T>);
+
+        impl<'de, T: Deserialize<'de>> de::Visitor<'de> for RootVisitor<T> {
+            type Value = ResponseRoot<T>;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str("an object with a shipments field or an array of shipments")
+            }
+
+            fn visit_seq<A: de::SeqAccess<'de>>(self, seq: A) -> Result<Self::Value, A::Error> {
+                let shipments = Vec::deserialize(de::value::SeqAccessDeserializer::new(seq))?;
+                Ok(ResponseRoot(ResponseFields { shipments, possible_additional_shipments_url: Vec::new() }))
+            }
+
+            fn visit_map<A: de::MapAccess<'de>>(self, map: A) -> Result<Self::Value, A::Error> {
+                ResponseFields::deserialize(de::value::MapAccessDeserializer::new(map)).map(ResponseRoot)
+            }
+        }
+
+        deserializer.deserialize_any(RootVisitor(PhantomData))
+    }
+}
+
+impl From<ResponseRoot<Shipment>> for Response {
+    fn from(ResponseRoot(fields): ResponseRoot<Shipment>) -> Response {
+        Response { shipments: fields.shipments, possible_additional_shipments_url: fields.possible_additional_shipments_url }
+    }
+}
+
+// Keeps the shipments that parse and reports the others by their index in the response
+pub fn parse_response_tolerant(json: &str) -> ClientResult<TolerantResponse> {
+    let ResponseRoot(raw): ResponseRoot<serde_json::Value> = serde_json::from_str(json)?;
+
+    let mut shipments = Vec::new();
+    let mut errors = Vec::new();
+    for (index, shipment) in raw.shipments.into_iter().enumerate() {
+        match serde_json::from_value(shipment) {
+            Ok(shipment) => shipments.push(shipment),
+            Err(err) => errors.push(ShipmentParseError { index, message: err.to_string() }),
+        }
+    }
+
+    Ok(TolerantResponse {
+        response: Response { shipments, possible_additional_shipments_url: raw.possible_additional_shipments_url },
+        errors,
+    })
+}
+
+fn deserialize_null_as_default<'de, D, T>(deserializer: D) -> Result<T, D::Error>
 where
-    D: de::Deserializer<'de>
+    D: de::Deserializer<'de>,
+    T: Default + Deserialize<'de>,
 {
-    let service_str = String::deserialize(deserializer)?;
-
-    Service::try_from(service_str.as_ref()).map_err(de::Error::custom)
+    Ok(Option::<T>::deserialize(deserializer)?.unwrap_or_default())
 }
 
-fn deserialize_status_code<'de, D>(deserializer: D) -> Result<Option<StatusCode>, D::Error>
+fn deserialize_physical_attributes<'de, D>(deserializer: D) -> Result<Option<PhysicalAttributes>, D::Error>
 where
     D: de::Deserializer<'de>
 {
-    let status_code_str = String::deserialize(deserializer)?;
+    let attributes = PhysicalAttributes::deserialize(deserializer)?;
 
-    if let Ok(code) = StatusCode::try_from(status_code_str.as_ref()) {
-        return Ok(Some(code))
+    if attributes.weight.is_none() && attributes.dimensions.is_none() {
+        Ok(None)
     } else {
-        return Err(de::Error::custom("Could not parse status code"))
+        Ok(Some(attributes))
+    }
+}
+
+// Some endpoints nest the code, as in {"statusCode": "transit", "status": "..."}
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawStatusCode {
+    Code(String),
+    #[serde(rename_all = "camelCase")]
+    Object { status_code: Option<String> },
+}
+
+// Returns the code along with the raw string when it had to fall back to Unknown
+fn parse_status_code(status_code: Option<RawStatusCode>) -> (Option<StatusCode>, Option<String>) {
+    // A null code is as good as a missing one
+    let status_code_str = match status_code {
+        Some(RawStatusCode::Code(status_code_str)) | Some(RawStatusCode::Object { status_code: Some(status_code_str) }) => status_code_str,
+        Some(RawStatusCode::Object { status_code: None }) | None => return (None, None),
+    };
+
+    // DHL may introduce new codes at any time, they shouldn't make the whole shipment unreadable
+    match StatusCode::try_from(status_code_str.as_ref()) {
+        Ok(status_code) => (Some(status_code), None),
+        Err(_) => {
+            trace_event!(warn, status_code = %status_code_str, "Unrecognized DHL status code");
+            (Some(StatusCode::Unknown), Some(status_code_str))
+        },
     }
 }
 
@@ -184,72 +790,1491 @@ where
 {
     let date_str = String::deserialize(deserializer)?;
 
-    let naive_date = NaiveDateTime::parse_from_str(&date_str, "%Y-%m-%dT%H:%M:%S");
-    if let Ok(naive_date) = naive_date {
-        return Ok(DateTime::<Utc>::from_utc(naive_date, Utc))
-    }
-
-    Err(de::Error::custom("Could not parse date"))
+    parse_dhl_datetime(&date_str).ok_or_else(|| de::Error::custom("Could not parse date"))
 }
 
-fn deserialize_dhl_date<'de, D>(deserializer: D) -> Result<DateTime<Utc>, D::Error>
-    where D: de::Deserializer<'de>
+fn deserialize_optional_dhl_datetime<'de, D>(deserializer: D) -> Result<Option<DateTime<Utc>>, D::Error>
+where
+    D: de::Deserializer<'de>
 {
-    let mut date_str = String::deserialize(deserializer)?;
-    date_str = format!("{} 00:00:01", date_str);
+    match Option::<String>::deserialize(deserializer)? {
+        Some(date_str) => parse_dhl_datetime(&date_str).map(Some).ok_or_else(|| de::Error::custom("Could not parse date")),
+        None => Ok(None),
+    }
+}
+
+fn parse_dhl_datetime(date_str: &str) -> Option<DateTime<Utc>> {
+    if let Ok(date) = DateTime::parse_from_rfc3339(date_str) {
+        return Some(date.with_timezone(&Utc))
+    }
 
-    let naive_date = NaiveDateTime::parse_from_str(&date_str, "%Y-%m-%d %H:%M:%S");
+    let naive_date = NaiveDateTime::parse_from_str(date_str, "%Y-%m-%dT%H:%M:%S")
+        .or_else(|_| NaiveDateTime::parse_from_str(date_str, "%Y-%m-%dT%H:%M:%S%.f"));
     if let Ok(naive_date) = naive_date {
-        return Ok(DateTime::<Utc>::from_utc(naive_date, Utc))
+        return Some(DateTime::<Utc>::from_naive_utc_and_offset(naive_date, Utc))
     }
 
-    Err(de::Error::custom("Could not parse date"))
+    None
 }
 
-#[derive(Clone)]
+// A bare date only tells the day, which is a window running until the next midnight
+fn parse_dhl_day(date_str: &str) -> Option<DeliveryEstimate> {
+    let day = NaiveDate::parse_from_str(date_str, "%Y-%m-%d").ok()?;
+    let from = DateTime::<Utc>::from_naive_utc_and_offset(day.and_hms_opt(0, 0, 0)?, Utc);
+
+    Some(DeliveryEstimate::Window { from, to: from + chrono::Duration::days(1) })
+}
+
+// Equality works on the normalized form, so "jjd 0001" and "JJD0001" are the same number
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct TrackingNumber {
     tracking_number: String,
 }
 
 impl TryFrom<&str> for TrackingNumber {
-    type Error = &'static str;
+    type Error = TrackingNumberError;
 
+    // Numbers are stored normalized: whitespace is dropped, as pasted numbers often come
+    // grouped like "JJD 0001 2345", and letters are uppercased since DHL ignores their case.
+    // Dashes are kept, they set air waybill and freight formats apart
     fn try_from(tracking_number: &str) -> Result<Self, Self::Error> {
-        let re = Regex::new(r"(^(\d{10})$)|(^(000|JJD01|JJD00|JVGL)\d+$)|(^(GM|LX|RX|[a-zA-Z]{5})\d+$)|(^(\d{10,39})$)|(^(3S|JVGL|JJD)[a-zA-Z0-9]+$)|(^\d{7}$)|(^\d[a-zA-Z]{2}\d{4,6}$)|(^[a-zA-Z]{3,4}\d+$)|(^\d{3}-\d{8}$)|(^[a-zA-Z]{2,3}-[a-zA-Z]{2,3}-\d{7}$)|(^\d{4}-\d{5}$)|(^\d{9,10}|\d{14}$)").unwrap();
+        let tracking_number = strip_whitespace(tracking_number);
+        validate_tracking_number(&tracking_number)?;
+
+        Ok(TrackingNumber { tracking_number: tracking_number.to_ascii_uppercase() })
+    }
+}
+
+// Same rules as TrackingNumber::try_from, without allocating for numbers free of inner whitespace
+pub fn is_valid_tracking_number(tracking_number: &str) -> bool {
+    validate_tracking_number(&strip_whitespace(tracking_number)).is_ok()
+}
+
+fn strip_whitespace(value: &str) -> Cow<'_, str> {
+    let value = value.trim();
+    if !value.contains(char::is_whitespace) { return Cow::Borrowed(value) }
 
-        if !re.is_match(tracking_number) { return Err("Tracking Number did not match DHL format") }
+    Cow::Owned(value.chars().filter(|c| !c.is_whitespace()).collect())
+}
 
-        Ok(TrackingNumber { tracking_number: tracking_number.trim().to_string() })
+fn validate_tracking_number(tracking_number: &str) -> Result<(), TrackingNumberError> {
+    // The shortest formats are a two-letter prefix with a digit, or seven digits
+    if tracking_number.is_empty() { return Err(TrackingNumberError::Empty) }
+    if tracking_number.len() < 3 || (tracking_number.len() < 7 && tracking_number.chars().all(|c| c.is_ascii_digit())) {
+        return Err(TrackingNumberError::TooShort)
     }
+
+    if !tracking_number_regex().is_match(tracking_number) { return Err(TrackingNumberError::UnrecognizedFormat) }
+
+    Ok(())
 }
 
-pub struct Client {
-    api_key: String,
+// Case insensitive, so that numbers can be checked before being uppercased
+fn tracking_number_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| {
+        Regex::new(r"(?i)(^(\d{10})$)|(^(000|JJD01|JJD00|JVGL)\d+$)|(^(GM|LX|RX|[a-zA-Z]{5})\d+$)|(^(\d{10,39})$)|(^(3S|JVGL|JJD)[a-zA-Z0-9]+$)|(^\d{7}$)|(^\d[a-zA-Z]{2}\d{4,6}$)|(^[a-zA-Z]{3,4}\d+$)|(^\d{3}-\d{8}$)|(^[a-zA-Z]{2,3}-[a-zA-Z]{2,3}-\d{7}$)|(^\d{4}-\d{5}$)|(^\d{9,10}$)|(^\d{14}$)").unwrap()
+    })
 }
 
-impl Client {
-    pub fn new(api_key: &str) -> Client {
-        Client { api_key: api_key.to_string() }
+impl TrackingNumber {
+    // Stricter than try_from: purely numeric numbers must also carry a valid Luhn check digit
+    pub fn try_from_checked(tracking_number: &str) -> Result<Self, TrackingNumberError> {
+        let tracking_number = TrackingNumber::try_from(tracking_number)?;
+
+        let digits = &tracking_number.tracking_number;
+        if digits.chars().all(|c| c.is_ascii_digit()) && !luhn_is_valid(digits) {
+            return Err(TrackingNumberError::InvalidCheckDigit)
+        }
+
+        Ok(tracking_number)
     }
 
-    pub async fn get_shipments(&self, tracking_number: TrackingNumber) ->  ClientResult<Response> {
-        let mut uri = "https://api-eu.dhl.com/track/shipments?trackingNumber=".to_string();
-        uri.push_str(&tracking_number.tracking_number);
+    pub fn as_str(&self) -> &str {
+        &self.tracking_number
+    }
 
-        let mut response = surf::get(uri)
-            .set_header("Accept", "application/json")
-            .set_header("DHL-API-KEY", &self.api_key)
-            .await?;
+    // Best guess from the number's shape, None when several services use the same one
+    pub fn detect_kind(&self) -> Option<Service> {
+        let number = self.tracking_number.to_ascii_uppercase();
+        let is_digits = |part: &str| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit());
 
-        match response.status() {
-            http::StatusCode::OK => {},
-            http::StatusCode::UNAUTHORIZED => return Err(Unauthorized),
-            http::StatusCode::NOT_FOUND => return Err(ParcelNotFound),
-            _ => return Err(ServerError),
+        if number.starts_with("JJD") || number.starts_with("JVGL") {
+            return Some(Service::ParcelDE)
+        }
+        if number.starts_with("3S") {
+            return Some(Service::ParcelNL)
+        }
+        if ["GM", "LX", "RX"].iter().any(|prefix| number.starts_with(prefix) && is_digits(&number[prefix.len()..])) {
+            return Some(Service::Ecommerce)
+        }
+        if number.len() == 10 && is_digits(&number) {
+            return Some(Service::Express)
         }
+        // Air waybills, like 020-12345678
+        let bytes = number.as_bytes();
+        if bytes.len() == 12 && bytes[3] == b'-' && bytes.iter().enumerate().all(|(i, b)| i == 3 || b.is_ascii_digit()) {
+            return Some(Service::DGF)
+        }
+
+        None
+    }
+}
+
+fn luhn_is_valid(digits: &str) -> bool {
+    let sum: u32 = digits.chars().rev()
+        .filter_map(|c| c.to_digit(10))
+        .enumerate()
+        .map(|(i, digit)| match (i % 2, digit * 2) {
+            (1, doubled) if doubled > 9 => doubled - 9,
+            (1, doubled) => doubled,
+            _ => digit,
+        })
+        .sum();
+
+    sum.is_multiple_of(10)
+}
+
+impl Serialize for TrackingNumber {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.tracking_number)
+    }
+}
+
+impl<'de> Deserialize<'de> for TrackingNumber {
+    fn deserialize<D: de::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let tracking_number = String::deserialize(deserializer)?;
+
+        TrackingNumber::try_from(tracking_number.as_ref()).map_err(de::Error::custom)
+    }
+}
+
+impl std::fmt::Display for TrackingNumber {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(&self.tracking_number)
+    }
+}
+
+impl AsRef<str> for TrackingNumber {
+    fn as_ref(&self) -> &str {
+        &self.tracking_number
+    }
+}
+
+impl From<TrackingNumber> for String {
+    fn from(tracking_number: TrackingNumber) -> String {
+        tracking_number.tracking_number
+    }
+}
+
+const DEFAULT_BASE_URL: &str = "https://api-eu.dhl.com/track/shipments";
+const US_BASE_URL: &str = "https://api-us.dhl.com/track/shipments";
+const SANDBOX_BASE_URL: &str = "https://api-test.dhl.com/track/shipments";
+const DEFAULT_API_KEY_VAR: &str = "DHL_API_KEY";
+pub(crate) const API_KEY_HEADER: &str = "DHL-API-KEY";
+const BATCH_CONCURRENCY: usize = 8;
+const MAX_LIMIT: u32 = 50;
+const DEFAULT_MAX_BODY_BYTES: usize = 10 * 1024 * 1024;
+const DEFAULT_USER_AGENT: &str = concat!("dhl-rs/", env!("CARGO_PKG_VERSION"));
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Region {
+    Eu,
+    Us,
+}
+
+impl Region {
+    fn base_url(self) -> &'static str {
+        match self {
+            Region::Eu => DEFAULT_BASE_URL,
+            Region::Us => US_BASE_URL,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_retries: 0,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn new(max_retries: u32) -> RetryPolicy {
+        RetryPolicy { max_retries, ..RetryPolicy::default() }
+    }
+
+    fn backoff(&self, attempt: u32) -> Duration {
+        let exponential = 2u32.checked_pow(attempt)
+            .and_then(|factor| self.initial_backoff.checked_mul(factor))
+            .map_or(self.max_backoff, |backoff| backoff.min(self.max_backoff));
+
+        // Cheap jitter so that clients failing together don't retry in lockstep,
+        // the delay ends up somewhere between half and all of the exponential one
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |now| now.subsec_nanos());
+        exponential / 2 + exponential.mul_f64(f64::from(nanos % 1000) / 2000.0)
+    }
+}
+
+// Quota as last reported by DHL, reset is how long until the quota refills
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RateLimit {
+    pub limit: Option<u64>,
+    pub remaining: Option<u64>,
+    pub reset: Option<Duration>,
+}
+
+impl RateLimit {
+    fn from_response(response: &HttpResponse) -> Option<RateLimit> {
+        let header = |name| response.header(name).and_then(|value| value.trim().parse::<u64>().ok());
+        let rate_limit = RateLimit {
+            limit: header("RateLimit-Limit"),
+            remaining: header("RateLimit-Remaining"),
+            reset: header("RateLimit-Reset").map(Duration::from_secs),
+        };
+
+        if rate_limit.limit.is_none() && rate_limit.remaining.is_none() && rate_limit.reset.is_none() {
+            return None
+        }
+        Some(rate_limit)
+    }
+}
+
+struct PagesState {
+    tracking_number: TrackingNumber,
+    pending: VecDeque<ClientResult<String>>,
+    visited: HashSet<String>,
+    shipments: VecDeque<Shipment>,
+    first_page: bool,
+}
+
+// Tells get_shipments whether a failed attempt is worth retrying
+enum AttemptError {
+    Transient { error: ClientError, retry_after: Option<Duration> },
+    Fatal(ClientError),
+}
+
+impl AttemptError {
+    fn transient(error: ClientError) -> AttemptError {
+        AttemptError::Transient { error, retry_after: None }
+    }
+}
+
+#[cfg(feature = "surf-backend")]
+type DefaultBackend = SurfBackend;
+#[cfg(all(feature = "reqwest-backend", not(feature = "surf-backend")))]
+type DefaultBackend = ReqwestBackend;
+
+// Clones share the backend, and with it the connection pool
+#[derive(Clone)]
+pub struct Client {
+    backend: Arc<dyn HttpBackend>,
+    api_key: String,
+    base_url: String,
+    timeout: Option<Duration>,
+    retry_policy: RetryPolicy,
+    requester_country_code: Option<String>,
+    rate_limit: Arc<Mutex<Option<RateLimit>>>,
+    user_agent: String,
+    max_body_bytes: usize,
+    extra_headers: Vec<(String, String)>,
+}
+
+impl Client {
+    // Panics on an empty key, which would otherwise only show up as a 401 on every request.
+    // with_base_url and the builder return MissingApiKey instead
+    pub fn new(api_key: impl Into<String>) -> Client {
+        let api_key = api_key.into();
+        assert!(!api_key.trim().is_empty(), "DHL API key must not be empty");
+
+        Client {
+            backend: Arc::new(DefaultBackend::new()),
+            api_key,
+            base_url: DEFAULT_BASE_URL.to_string(),
+            timeout: None,
+            retry_policy: RetryPolicy::default(),
+            requester_country_code: None,
+            rate_limit: Arc::new(Mutex::new(None)),
+            user_agent: DEFAULT_USER_AGENT.to_string(),
+            max_body_bytes: DEFAULT_MAX_BODY_BYTES,
+            extra_headers: Vec::new(),
+        }
+    }
+
+    pub fn with_base_url(api_key: impl Into<String>, base_url: &str) -> ClientResult<Client> {
+        let api_key = api_key.into();
+        if api_key.trim().is_empty() { return Err(MissingApiKey) }
+        let url = Url::parse(base_url.trim()).map_err(|_| InvalidBaseUrl)?;
+        // shipments_uri appends the query itself, so the root can't carry one
+        if url.cannot_be_a_base() || url.query().is_some() || url.fragment().is_some() { return Err(InvalidBaseUrl) }
+
+        let mut client = Client::new(api_key);
+        client.base_url = url.as_str().to_string();
+        Ok(client)
+    }
+
+    // Client::new targets Region::Eu
+    pub fn for_region(region: Region, api_key: impl Into<String>) -> Client {
+        let mut client = Client::new(api_key);
+        client.base_url = region.base_url().to_string();
+        client
+    }
+
+    // DHL's sandbox serves canned data and has its own quota, sandbox keys only work there
+    pub fn sandbox(api_key: impl Into<String>) -> Client {
+        let mut client = Client::new(api_key);
+        client.base_url = SANDBOX_BASE_URL.to_string();
+        client
+    }
+
+    pub fn from_env() -> ClientResult<Client> {
+        Client::from_env_var(DEFAULT_API_KEY_VAR)
+    }
+
+    pub fn from_env_var(name: &str) -> ClientResult<Client> {
+        match std::env::var(name) {
+            Ok(api_key) if !api_key.trim().is_empty() => Ok(Client::new(api_key.trim())),
+            _ => Err(MissingEnvVar { name: name.to_string() }),
+        }
+    }
+
+    pub fn builder() -> ClientBuilder {
+        ClientBuilder::default()
+    }
+
+    pub fn set_backend(&mut self, backend: impl HttpBackend + 'static) {
+        self.backend = Arc::new(backend);
+    }
+
+    // The timeout covers the whole exchange, from connecting to reading the body
+    pub fn set_timeout(&mut self, timeout: Option<Duration>) {
+        self.timeout = timeout;
+    }
+
+    // Only 5xx, 429, transport errors and timeouts are retried
+    pub fn set_retry_policy(&mut self, retry_policy: RetryPolicy) {
+        self.retry_policy = retry_policy;
+    }
+
+    // Defaults to dhl-rs/<version>
+    pub fn set_user_agent(&mut self, user_agent: &str) -> ClientResult<()> {
+        validate_header("User-Agent", user_agent)?;
+        self.user_agent = user_agent.to_string();
+        Ok(())
+    }
+
+    // Defaults to 10 MiB, which no tracking response comes close to
+    pub fn set_max_body_bytes(&mut self, max_body_bytes: usize) {
+        self.max_body_bytes = max_body_bytes;
+    }
+
+    // Sent along with every request unless a ShipmentQuery sets its own
+    pub fn set_requester_country_code(&mut self, requester_country_code: Option<&str>) -> ClientResult<()> {
+        self.requester_country_code = match requester_country_code {
+            Some(requester_country_code) => Some(validate_country_code(requester_country_code)?.to_string()),
+            None => None,
+        };
+        Ok(())
+    }
+
+    // Shared between clones, as they draw from the same quota
+    pub fn rate_limit(&self) -> Option<RateLimit> {
+        self.rate_limit.lock().map_or(None, |rate_limit| rate_limit.clone())
+    }
+
+    // Shorthand for get_shipments that validates the number itself
+    pub async fn track(&self, tracking_number: &str) -> ClientResult<Response> {
+        let tracking_number = TrackingNumber::try_from(tracking_number)
+            .map_err(|reason| InvalidTrackingNumber { tracking_number: tracking_number.to_string(), reason })?;
+
+        self.get_shipments(tracking_number).await
+    }
+
+    pub async fn get_shipments(&self, tracking_number: TrackingNumber) ->  ClientResult<Response> {
+        self.get_shipments_raw(tracking_number).await.map(|(response, _)| response)
+    }
+
+    // Pair the registration with an AbortHandle from AbortHandle::new_pair, aborting yields Cancelled
+    pub async fn get_shipments_abortable(&self, tracking_number: TrackingNumber, registration: AbortRegistration) -> ClientResult<Response> {
+        Abortable::new(self.get_shipments(tracking_number), registration).await.unwrap_or(Err(Cancelled))
+    }
+
+    // Also hands back the body as DHL sent it, handy to keep around for auditing
+    pub async fn get_shipments_raw(&self, tracking_number: TrackingNumber) -> ClientResult<(Response, String)> {
+        let uri = self.query_uri(&ShipmentQuery::new(tracking_number.clone()))?;
+        let request = self.get_response(&tracking_number, &uri);
+
+        #[cfg(feature = "tracing")]
+        let request = tracing::Instrument::instrument(
+            request,
+            tracing::info_span!("get_shipments", tracking_number = %redact(tracking_number.as_str())),
+        );
+
+        request.await
+    }
+
+    // The language is a two-letter ISO 639-1 code, like "de"
+    pub async fn get_shipments_with_language(&self, tracking_number: TrackingNumber, language: &str) -> ClientResult<Response> {
+        self.get(ShipmentQuery::new(tracking_number).language(language)).await
+    }
+
+    pub async fn get_shipments_for_service(&self, tracking_number: TrackingNumber, service: Service) -> ClientResult<Response> {
+        self.get(ShipmentQuery::new(tracking_number).service(service)).await
+    }
+
+    // Cross-border parcels sometimes only show up once the origin country and recipient postal code are given
+    pub async fn get_shipments_with_origin(&self, tracking_number: TrackingNumber, origin_country_code: Option<&str>, recipient_postal_code: Option<&str>) -> ClientResult<Response> {
+        let mut query = ShipmentQuery::new(tracking_number);
+        if let Some(origin_country_code) = origin_country_code { query = query.origin_country_code(origin_country_code) }
+        if let Some(recipient_postal_code) = recipient_postal_code { query = query.recipient_postal_code(recipient_postal_code) }
+
+        self.get(query).await
+    }
+
+    // Manual paging, as an alternative to following possibleAdditionalShipmentsUrl.
+    // Pages past the end come back with no shipments rather than NoShipments
+    pub async fn get_shipments_page(&self, tracking_number: TrackingNumber, offset: u32, limit: u32) -> ClientResult<Response> {
+        let uri = self.query_uri(&ShipmentQuery::new(tracking_number.clone()).offset(offset).limit(limit))?;
+        self.get_page(&tracking_number, &uri).await.map(|(response, _)| response)
+    }
+
+    pub async fn get(&self, query: ShipmentQuery) -> ClientResult<Response> {
+        let uri = self.query_uri(&query)?;
+        self.get_response(&query.tracking_number, &uri).await.map(|(response, _)| response)
+    }
+
+    // Asks the service detect_kind guesses first, then the others, until one knows the number.
+    // Errors other than not found, like Unauthorized, end the search right away
+    pub async fn resolve_shipment(&self, tracking_number: TrackingNumber) -> ClientResult<Response> {
+        let guess = tracking_number.detect_kind();
+        let services = guess.iter().cloned()
+            .chain(Service::ALL.iter().filter(|service| Some(*service) != guess.as_ref()).cloned());
+
+        let mut last_error = NoShipments;
+        for service in services {
+            match self.get_shipments_for_service(tracking_number.clone(), service).await {
+                Ok(response) => return Ok(response),
+                Err(err @ NoShipments) | Err(err @ ParcelNotFound { .. }) => last_error = err,
+                Err(err) => return Err(err),
+            }
+        }
+
+        Err(last_error)
+    }
+
+    // Resolves once the shipment is delivered or failed, or with a Timeout after max_wait
+    pub async fn poll_until_delivered(&self, tracking_number: TrackingNumber, interval: Duration, max_wait: Duration) -> ClientResult<Shipment> {
+        let poll = async {
+            loop {
+                match self.get_shipments(tracking_number.clone()).await {
+                    Ok(response) => {
+                        let shipment = response.shipments.into_iter().next().ok_or(NoShipments)?;
+                        if shipment.status.status_code.as_ref().is_some_and(StatusCode::is_terminal) {
+                            return Ok(shipment)
+                        }
+                    },
+                    // Freshly created parcels often have no data yet
+                    Err(NoShipments) => {},
+                    Err(err) => return Err(err),
+                }
+
+                Delay::new(interval).await;
+            }
+        };
+
+        with_timeout(poll, max_wait).await.unwrap_or(Err(Timeout))
+    }
+
+    pub async fn download_proof_of_delivery(&self, proof_of_delivery: &ProofOfDelivery) -> ClientResult<Vec<u8>> {
+        let request = async {
+            // The URL comes from a response a gateway may have rewritten, so the key only goes to the API's own host
+            Url::parse(&proof_of_delivery.document_url)
+                .map_err(|err| InvalidResponse { message: format!("Invalid document URL {}: {}", proof_of_delivery.document_url, err) })?;
+            let mut headers = vec![("User-Agent".to_string(), self.user_agent.clone())];
+            if self.is_api_host(&proof_of_delivery.document_url) {
+                headers.insert(0, (API_KEY_HEADER.to_string(), self.api_key.clone()));
+            }
+
+            let response = self.backend.send(HttpRequest {
+                url: proof_of_delivery.document_url.clone(),
+                headers: self.with_extra_headers(headers),
+                max_body_bytes: self.max_body_bytes,
+            }).await?;
+            if response.body.len() > self.max_body_bytes { return Err(ResponseTooLarge { limit: self.max_body_bytes }) }
+
+            match response.status {
+                200 => response_body(response, self.max_body_bytes),
+                401 => Err(Unauthorized { api_error: parse_api_error(&response.body) }),
+                404 => Err(DocumentNotAvailable),
+                429 => Err(RateLimited { retry_after: parse_retry_after(response.header("Retry-After")) }),
+                status => Err(unexpected_status(status, &response.body)),
+            }
+        };
+
+        match self.timeout {
+            Some(timeout) => with_timeout(request, timeout).await.unwrap_or(Err(Timeout)),
+            None => request.await,
+        }
+    }
+
+    // Walks possibleAdditionalShipmentsUrl, including the ones found on additional pages
+    pub async fn get_all_shipments(&self, tracking_number: TrackingNumber) -> ClientResult<Vec<Shipment>> {
+        let uri = self.query_uri(&ShipmentQuery::new(tracking_number.clone()))?;
+        let (first, _) = self.get_response(&tracking_number, &uri).await?;
+
+        let mut shipments = first.shipments;
+        let mut pending: VecDeque<String> = first.possible_additional_shipments_url.into();
+        let mut visited = HashSet::new();
+        visited.insert(uri);
+
+        while let Some(additional_url) = pending.pop_front() {
+            let uri = self.resolve_url(&additional_url)?;
+            if !visited.insert(uri.clone()) { continue }
+
+            let (page, _) = self.get_page(&tracking_number, &uri).await?;
+            shipments.extend(page.shipments);
+            pending.extend(page.possible_additional_shipments_url);
+        }
+
+        Ok(shipments)
+    }
+
+    // Lazy take on get_all_shipments, a failing page yields its error and the stream goes on with the others
+    pub fn shipments_stream(&self, tracking_number: TrackingNumber) -> impl Stream<Item = ClientResult<Shipment>> + '_ {
+        let state = PagesState {
+            pending: vec![self.query_uri(&ShipmentQuery::new(tracking_number.clone()))].into(),
+            tracking_number,
+            visited: HashSet::new(),
+            shipments: VecDeque::new(),
+            first_page: true,
+        };
+
+        stream::unfold(state, move |mut state| async move {
+            loop {
+                if let Some(shipment) = state.shipments.pop_front() { return Some((Ok(shipment), state)) }
+
+                let uri = match state.pending.pop_front()? {
+                    Ok(uri) => uri,
+                    Err(err) => return Some((Err(err), state)),
+                };
+                if !state.visited.insert(uri.clone()) { continue }
+
+                let page = if state.first_page {
+                    self.get_response(&state.tracking_number, &uri).await
+                } else {
+                    self.get_page(&state.tracking_number, &uri).await
+                };
+                state.first_page = false;
+
+                match page {
+                    Ok((page, _)) => {
+                        state.shipments.extend(page.shipments);
+                        state.pending.extend(page.possible_additional_shipments_url.iter().map(|url| self.resolve_url(url)));
+                    },
+                    Err(err) => return Some((Err(err), state)),
+                }
+            }
+        })
+    }
+
+    fn is_api_host(&self, url: &str) -> bool {
+        match (Url::parse(url), Url::parse(&self.base_url)) {
+            (Ok(url), Ok(base_url)) => url.host_str().is_some() && url.host_str() == base_url.host_str(),
+            _ => false,
+        }
+    }
+
+    fn shipments_uri(&self, tracking_number: &TrackingNumber) -> String {
+        // The known formats have no reserved characters, but a '/' or '+' must never end up raw in the query
+        let mut uri = format!("{}?trackingNumber=", self.base_url);
+        uri.extend(form_urlencoded::byte_serialize(tracking_number.tracking_number.as_bytes()));
+        uri
+    }
+
+    // Parameters are validated here rather than in the setters so that ShipmentQuery stays infallible
+    fn query_uri(&self, query: &ShipmentQuery) -> ClientResult<String> {
+        let mut uri = self.shipments_uri(&query.tracking_number);
+        if let Some(ref service) = query.service {
+            uri.push_str("&service=");
+            uri.push_str(service.as_str());
+        }
+        if let Some(requester_country_code) = query.requester_country_code.as_ref().or(self.requester_country_code.as_ref()) {
+            uri.push_str("&requesterCountryCode=");
+            uri.push_str(validate_country_code(requester_country_code)?);
+        }
+        if let Some(ref origin_country_code) = query.origin_country_code {
+            uri.push_str("&originCountryCode=");
+            uri.push_str(validate_country_code(origin_country_code)?);
+        }
+        if let Some(ref recipient_postal_code) = query.recipient_postal_code {
+            uri.push_str("&recipientPostalCode=");
+            uri.extend(form_urlencoded::byte_serialize(recipient_postal_code.trim().as_bytes()));
+        }
+        if let Some(ref language) = query.language {
+            let language = language.trim();
+            if language.len() != 2 || !language.chars().all(|c| c.is_ascii_alphabetic()) {
+                return Err(InvalidLanguage)
+            }
+            uri.push_str("&language=");
+            uri.push_str(&language.to_ascii_lowercase());
+        }
+        if let Some(offset) = query.offset {
+            uri.push_str(&format!("&offset={}", offset));
+        }
+        if let Some(limit) = query.limit {
+            if limit == 0 || limit > MAX_LIMIT { return Err(InvalidLimit { limit, max: MAX_LIMIT }) }
+            uri.push_str(&format!("&limit={}", limit));
+        }
+
+        Ok(uri)
+    }
+
+    // DHL hands out additional URLs relative to the track API root, like "/shipments?...".
+    // They come from the body, so an absolute one elsewhere must not be sent the key
+    fn resolve_url(&self, url: &str) -> ClientResult<String> {
+        let base = Url::parse(&self.base_url).map_err(|_| InvalidBaseUrl)?;
+        let resolved = base.join(url.trim_start_matches('/'))
+            .map_err(|err| InvalidResponse { message: format!("Invalid additional shipments URL {}: {}", url, err) })?;
+        if resolved.origin() != base.origin() {
+            return Err(InvalidResponse { message: format!("Additional shipments URL {} is not on the API host", url) })
+        }
+
+        Ok(resolved.to_string())
+    }
+
+    // DHL answers 200 with no shipments for valid numbers it has no data for yet
+    async fn get_response(&self, tracking_number: &TrackingNumber, uri: &str) -> ClientResult<(Response, String)> {
+        let (response, body) = self.get_page(tracking_number, uri).await?;
+        if response.shipments.is_empty() { return Err(NoShipments) }
+
+        Ok((response, body))
+    }
+
+    async fn get_page(&self, tracking_number: &TrackingNumber, uri: &str) -> ClientResult<(Response, String)> {
+        let mut attempt = 0;
+        loop {
+            match self.fetch_response(tracking_number, uri).await {
+                Ok(response) => return Ok(response),
+                // A Retry-After past max_backoff is left to the caller rather than slept through
+                Err(AttemptError::Transient { retry_after, .. })
+                    if attempt < self.retry_policy.max_retries && retry_after.is_none_or(|retry_after| retry_after <= self.retry_policy.max_backoff) =>
+                {
+                    let delay = retry_after.unwrap_or_else(|| self.retry_policy.backoff(attempt));
+                    trace_event!(info, attempt = attempt + 1, ?delay, "Retrying DHL request");
+
+                    Delay::new(delay).await;
+                    attempt += 1;
+                },
+                Err(AttemptError::Transient { error, .. }) | Err(AttemptError::Fatal(error)) => return Err(error),
+            }
+        }
+    }
+
+    // Results are returned in the same order as the tracking numbers
+    pub async fn get_shipments_batch(&self, tracking_numbers: &[TrackingNumber]) -> Vec<ClientResult<Response>> {
+        stream::iter(tracking_numbers)
+            .map(|tracking_number| self.get_shipments(tracking_number.clone()))
+            .buffered(BATCH_CONCURRENCY)
+            .collect()
+            .await
+    }
+
+    // Like get_shipments_batch, with each outcome next to its number
+    pub async fn track_many(&self, tracking_numbers: impl IntoIterator<Item = TrackingNumber>) -> Vec<(TrackingNumber, ClientResult<Response>)> {
+        stream::iter(tracking_numbers)
+            .map(|tracking_number| async move {
+                let result = self.get_shipments(tracking_number.clone()).await;
+                (tracking_number, result)
+            })
+            .buffered(BATCH_CONCURRENCY)
+            .collect()
+            .await
+    }
+
+    fn request(&self, uri: &str) -> HttpRequest {
+        let mut headers = vec![
+            ("Accept".to_string(), "application/json".to_string()),
+            (API_KEY_HEADER.to_string(), self.api_key.clone()),
+            ("User-Agent".to_string(), self.user_agent.clone()),
+        ];
+        if cfg!(feature = "compression") {
+            headers.push(("Accept-Encoding".to_string(), "gzip, deflate".to_string()));
+        }
+
+        HttpRequest { url: uri.to_string(), headers: self.with_extra_headers(headers), max_body_bytes: self.max_body_bytes }
+    }
+
+    // Extra headers never override the ones the client sets itself
+    fn with_extra_headers(&self, mut headers: Vec<(String, String)>) -> Vec<(String, String)> {
+        let extra_headers: Vec<(String, String)> = self.extra_headers.iter()
+            .filter(|(name, _)| !headers.iter().any(|(builtin, _)| builtin.eq_ignore_ascii_case(name)))
+            .cloned()
+            .collect();
+
+        headers.extend(extra_headers);
+        headers
+    }
+
+    async fn fetch_response(&self, tracking_number: &TrackingNumber, uri: &str) -> Result<(Response, String), AttemptError> {
+        let request = async {
+            let response = self.backend.send(self.request(uri))
+                .await
+                .map_err(|err| match err {
+                    ResponseTooLarge { .. } => AttemptError::Fatal(err),
+                    err => AttemptError::transient(err),
+                })?;
+
+            self.handle_response(tracking_number, response)
+        };
+
+        match self.timeout {
+            Some(timeout) => with_timeout(request, timeout).await
+                .unwrap_or_else(|| Err(AttemptError::transient(Timeout))),
+            None => request.await,
+        }
+    }
+
+    fn handle_response(&self, tracking_number: &TrackingNumber, response: HttpResponse) -> Result<(Response, String), AttemptError> {
+        // Custom backends may not enforce the limit themselves
+        if response.body.len() > self.max_body_bytes {
+            return Err(AttemptError::Fatal(ResponseTooLarge { limit: self.max_body_bytes }))
+        }
+
+        if let Some(rate_limit) = RateLimit::from_response(&response) {
+            if let Ok(mut latest) = self.rate_limit.lock() { *latest = Some(rate_limit) }
+        }
+
+        if response.status != 200 {
+            trace_event!(warn, status = response.status, "DHL answered with a non-200 status");
+        }
+
+        match response.status {
+            200 => {},
+            401 => return Err(AttemptError::Fatal(Unauthorized { api_error: parse_api_error(&response.body) })),
+            404 => return Err(AttemptError::Fatal(ParcelNotFound {
+                tracking_number: tracking_number.as_str().to_string(),
+                api_error: parse_api_error(&response.body),
+            })),
+            429 => {
+                let retry_after = parse_retry_after(response.header("Retry-After"));
+                return Err(AttemptError::Transient { error: RateLimited { retry_after }, retry_after })
+            },
+            status => {
+                let error = unexpected_status(status, &response.body);
+
+                return Err(match status {
+                    500..=599 => AttemptError::transient(error),
+                    _ => AttemptError::Fatal(error),
+                })
+            },
+        }
+
+        let body = response_body(response, self.max_body_bytes).map_err(AttemptError::Fatal)?;
+        let body = String::from_utf8(body)
+            .map_err(|err| AttemptError::Fatal(InvalidResponse { message: err.to_string() }))?;
+        let response = parse_response(&body).map_err(|err| {
+            trace_event!(error, error = %err, "Could not parse DHL response");
+            AttemptError::Fatal(err)
+        })?;
+
+        Ok((response, body))
+    }
+}
+
+impl std::fmt::Debug for Client {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("Client")
+            .field("api_key", &redact(&self.api_key))
+            .field("base_url", &self.base_url)
+            .field("timeout", &self.timeout)
+            .field("retry_policy", &self.retry_policy)
+            .field("requester_country_code", &self.requester_country_code)
+            .field("rate_limit", &self.rate_limit())
+            .field("user_agent", &self.user_agent)
+            .field("max_body_bytes", &self.max_body_bytes)
+            // Values may hold credentials, like proxy authorizations
+            .field("extra_headers", &self.extra_headers.iter().map(|(name, _)| name).collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+// Keeps the last four characters of long enough secrets so keys can still be told apart
+pub(crate) fn redact(secret: &str) -> String {
+    let chars: Vec<char> = secret.chars().collect();
+    if chars.len() <= 8 { return "***".to_string() }
+
+    let suffix: String = chars[chars.len() - 4..].iter().collect();
+    format!("***{}", suffix)
+}
+
+// Splits what track_many returns into successes and failures, keeping the order within each
+#[allow(clippy::type_complexity)]
+pub fn partition_results(results: Vec<(TrackingNumber, ClientResult<Response>)>) -> (Vec<(TrackingNumber, Response)>, Vec<(TrackingNumber, ClientError)>) {
+    let mut successes = Vec::new();
+    let mut failures = Vec::new();
+    for (tracking_number, result) in results {
+        match result {
+            Ok(response) => successes.push((tracking_number, response)),
+            Err(err) => failures.push((tracking_number, err)),
+        }
+    }
+
+    (successes, failures)
+}
+
+fn validate_country_code(country_code: &str) -> ClientResult<&str> {
+    let country_code = country_code.trim();
+    if country_code.len() != 2 || !country_code.chars().all(|c| c.is_ascii_uppercase()) {
+        return Err(InvalidCountryCode)
+    }
+
+    Ok(country_code)
+}
+
+// Names are RFC 7230 tokens and values visible ASCII, spaces and tabs, which is what
+// every backend accepts. Anything else would only fail once the request is sent
+fn validate_header(name: &str, value: &str) -> ClientResult<()> {
+    let is_token = !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || "!#$%&'*+-.^_`|~".contains(c));
+    let is_value = value.chars().all(|c| c == '\t' || (' '..='~').contains(&c));
+    if !is_token || !is_value { return Err(InvalidHeader { name: name.to_string() }) }
+
+    Ok(())
+}
+
+fn unexpected_status(status: u16, body: &[u8]) -> ClientError {
+    UnexpectedStatus {
+        status,
+        body: String::from_utf8_lossy(body).into_owned(),
+        api_error: parse_api_error(body),
+    }
+}
+
+// Bodies that aren't problem details, or carry no message, are not worth keeping
+fn parse_api_error(body: &[u8]) -> Option<DhlApiError> {
+    serde_json::from_slice(body).ok()
+        .filter(|api_error: &DhlApiError| api_error.title.is_some() || api_error.detail.is_some())
+}
+
+// The body is already capped by the caller, only a decoded one can still grow past max_body_bytes
+#[cfg(not(feature = "compression"))]
+fn response_body(response: HttpResponse, _max_body_bytes: usize) -> ClientResult<Vec<u8>> {
+    Ok(response.body)
+}
+
+// surf inflates bodies on its own but leaves Content-Encoding in place,
+// so the gzip and zlib magic bytes tell whether there is anything left to do
+#[cfg(feature = "compression")]
+fn response_body(response: HttpResponse, max_body_bytes: usize) -> ClientResult<Vec<u8>> {
+    use flate2::read::{GzDecoder, ZlibDecoder};
+    use std::io::Read;
+
+    let encoding = response.header("Content-Encoding").map(|encoding| encoding.trim().to_ascii_lowercase());
+    let body = response.body;
+
+    // One byte past the limit is enough to tell a body that is too large once inflated
+    let limit = max_body_bytes as u64 + 1;
+    let mut decoded = Vec::new();
+    let result = match encoding.as_deref() {
+        Some("gzip") if body.starts_with(&[0x1f, 0x8b]) => GzDecoder::new(&body[..]).take(limit).read_to_end(&mut decoded),
+        Some("deflate") if body.starts_with(&[0x78]) => ZlibDecoder::new(&body[..]).take(limit).read_to_end(&mut decoded),
+        _ => return Ok(body),
+    };
+
+    // A corrupt body is the server's doing, not the transport's
+    result.map_err(|err| InvalidResponse { message: err.to_string() })?;
+    if decoded.len() > max_body_bytes { return Err(ResponseTooLarge { limit: max_body_bytes }) }
+
+    Ok(decoded)
+}
+
+// Retry-After is either a number of seconds or an HTTP-date like "Wed, 21 Oct 2015 07:28:00 GMT"
+fn parse_retry_after(header: Option<&str>) -> Option<Duration> {
+    let header = header?.trim();
+    if let Ok(seconds) = header.parse() { return Some(Duration::from_secs(seconds)) }
+
+    let date = DateTime::parse_from_rfc2822(header).ok()?;
+    Some((date.with_timezone(&Utc) - Utc::now()).to_std().unwrap_or_default())
+}
+
+async fn with_timeout<F: Future>(request: F, timeout: Duration) -> Option<F::Output> {
+    futures::pin_mut!(request);
+
+    match future::select(request, Delay::new(timeout)).await {
+        Either::Left((result, _)) => Some(result),
+        Either::Right(_) => None,
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ShipmentQuery {
+    tracking_number: TrackingNumber,
+    service: Option<Service>,
+    language: Option<String>,
+    offset: Option<u32>,
+    limit: Option<u32>,
+    origin_country_code: Option<String>,
+    recipient_postal_code: Option<String>,
+    requester_country_code: Option<String>,
+}
+
+impl ShipmentQuery {
+    pub fn new(tracking_number: TrackingNumber) -> ShipmentQuery {
+        ShipmentQuery {
+            tracking_number,
+            service: None,
+            language: None,
+            offset: None,
+            limit: None,
+            origin_country_code: None,
+            recipient_postal_code: None,
+            requester_country_code: None,
+        }
+    }
+
+    pub fn service(mut self, service: Service) -> ShipmentQuery {
+        self.service = Some(service);
+        self
+    }
+
+    pub fn language(mut self, language: &str) -> ShipmentQuery {
+        self.language = Some(language.to_string());
+        self
+    }
+
+    pub fn offset(mut self, offset: u32) -> ShipmentQuery {
+        self.offset = Some(offset);
+        self
+    }
+
+    pub fn limit(mut self, limit: u32) -> ShipmentQuery {
+        self.limit = Some(limit);
+        self
+    }
+
+    pub fn origin_country_code(mut self, origin_country_code: &str) -> ShipmentQuery {
+        self.origin_country_code = Some(origin_country_code.to_string());
+        self
+    }
+
+    pub fn recipient_postal_code(mut self, recipient_postal_code: &str) -> ShipmentQuery {
+        self.recipient_postal_code = Some(recipient_postal_code.to_string());
+        self
+    }
+
+    pub fn requester_country_code(mut self, requester_country_code: &str) -> ShipmentQuery {
+        self.requester_country_code = Some(requester_country_code.to_string());
+        self
+    }
+}
+
+#[derive(Default)]
+pub struct ClientBuilder {
+    api_key: Option<String>,
+    base_url: Option<String>,
+    backend: Option<Arc<dyn HttpBackend>>,
+    timeout: Option<Duration>,
+    retry_policy: RetryPolicy,
+    requester_country_code: Option<String>,
+    sandbox: bool,
+    user_agent: Option<String>,
+    max_body_bytes: Option<usize>,
+    extra_headers: Vec<(String, String)>,
+}
+
+impl ClientBuilder {
+    pub fn api_key(mut self, api_key: impl Into<String>) -> ClientBuilder {
+        self.api_key = Some(api_key.into());
+        self
+    }
+
+    pub fn base_url(mut self, base_url: &str) -> ClientBuilder {
+        self.base_url = Some(base_url.to_string());
+        self
+    }
+
+    // An explicit base_url takes precedence
+    pub fn sandbox(mut self, sandbox: bool) -> ClientBuilder {
+        self.sandbox = sandbox;
+        self
+    }
+
+    pub fn backend(mut self, backend: impl HttpBackend + 'static) -> ClientBuilder {
+        self.backend = Some(Arc::new(backend));
+        self
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> ClientBuilder {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn max_retries(mut self, max_retries: u32) -> ClientBuilder {
+        self.retry_policy.max_retries = max_retries;
+        self
+    }
+
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> ClientBuilder {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    pub fn user_agent(mut self, user_agent: &str) -> ClientBuilder {
+        self.user_agent = Some(user_agent.to_string());
+        self
+    }
+
+    // Sent with every request, except where it collides with a header the client sets itself
+    pub fn header(mut self, name: &str, value: &str) -> ClientBuilder {
+        self.extra_headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    pub fn max_body_bytes(mut self, max_body_bytes: usize) -> ClientBuilder {
+        self.max_body_bytes = Some(max_body_bytes);
+        self
+    }
+
+    pub fn requester_country_code(mut self, requester_country_code: &str) -> ClientBuilder {
+        self.requester_country_code = Some(requester_country_code.to_string());
+        self
+    }
+
+    pub fn build(self) -> ClientResult<Client> {
+        let api_key = self.api_key.filter(|api_key| !api_key.trim().is_empty()).ok_or(MissingApiKey)?;
+
+        let mut client = match self.base_url {
+            Some(base_url) => Client::with_base_url(api_key, &base_url)?,
+            None if self.sandbox => Client::sandbox(api_key),
+            None => Client::new(api_key),
+        };
+        if let Some(backend) = self.backend { client.backend = backend }
+        client.timeout = self.timeout;
+        client.retry_policy = self.retry_policy;
+        client.set_requester_country_code(self.requester_country_code.as_deref())?;
+        if let Some(ref user_agent) = self.user_agent { client.set_user_agent(user_agent)? }
+        if let Some(max_body_bytes) = self.max_body_bytes { client.max_body_bytes = max_body_bytes }
+        for (name, value) in &self.extra_headers {
+            validate_header(name, value)?;
+        }
+        client.extra_headers = self.extra_headers;
+
+        Ok(client)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use chrono::TimeZone;
+
+    const STATUS_CODES: [StatusCode; 5] = [
+        StatusCode::PreTransit,
+        StatusCode::Transit,
+        StatusCode::Delivered,
+        StatusCode::Failure,
+        StatusCode::Unknown,
+    ];
+
+    // One shipment with only the fields DHL always sends, extra ones are appended after them
+    fn response_json(extra_fields: &str) -> String {
+        format!(
+            r#"{{"shipments":[{{"id":"1","service":"express","status":{{"timestamp":"2023-01-05T13:22:00"}},"details":{{}},"events":[]{}}}]}}"#,
+            extra_fields,
+        )
+    }
+
+    // Answers every request with the same response and keeps the requests it was sent
+    #[derive(Clone)]
+    struct MockBackend {
+        response: HttpResponse,
+        requests: Arc<Mutex<Vec<HttpRequest>>>,
+    }
+
+    impl MockBackend {
+        fn new(status: u16, headers: Vec<(String, String)>, body: Vec<u8>) -> MockBackend {
+            MockBackend { response: HttpResponse { status, headers, body }, requests: Arc::new(Mutex::new(Vec::new())) }
+        }
+
+        fn sent_header(&self, index: usize, name: &str) -> Option<String> {
+            let requests = self.requests.lock().unwrap();
+            requests[index].headers.iter().find(|(header, _)| header == name).map(|(_, value)| value.clone())
+        }
+    }
+
+    impl HttpBackend for MockBackend {
+        fn send(&self, request: HttpRequest) -> futures::future::BoxFuture<'_, ClientResult<HttpResponse>> {
+            self.requests.lock().unwrap().push(request);
+            let response = self.response.clone();
+            Box::pin(async move { Ok(response) })
+        }
+    }
+
+    fn parse_shipment(extra_fields: &str) -> ClientResult<Shipment> {
+        parse_response(&response_json(extra_fields)).map(|response| response.shipments.into_iter().next().unwrap())
+    }
+
+    #[test]
+    fn service_try_from_round_trips() {
+        for service in Service::ALL.iter() {
+            assert_eq!(Service::try_from(service.as_str()), Ok(service.clone()));
+        }
+    }
+
+    #[test]
+    fn display_round_trips_through_try_from() {
+        for service in Service::ALL.iter() {
+            assert_eq!(Service::try_from(service.to_string().as_str()), Ok(service.clone()));
+        }
+        for status_code in STATUS_CODES.iter() {
+            assert_eq!(StatusCode::try_from(status_code.to_string().as_str()), Ok(status_code.clone()));
+        }
+    }
+
+    #[test]
+    fn serde_round_trips_through_dhl_strings() {
+        for service in Service::ALL.iter() {
+            let json = serde_json::to_string(service).unwrap();
+            assert_eq!(json, format!("\"{}\"", service.as_str()));
+            assert_eq!(&serde_json::from_str::<Service>(&json).unwrap(), service);
+        }
+        for status_code in STATUS_CODES.iter() {
+            let json = serde_json::to_string(status_code).unwrap();
+            assert_eq!(json, format!("\"{}\"", status_code.as_str()));
+            assert_eq!(&serde_json::from_str::<StatusCode>(&json).unwrap(), status_code);
+        }
+    }
+
+    // The 9-10 and 14 digit alternatives used to be anchored on one side only
+    #[test]
+    fn tracking_number_rejects_trailing_junk() {
+        assert!(is_valid_tracking_number("123456789"));
+        assert!(!is_valid_tracking_number("123456789!"));
+        assert!(!is_valid_tracking_number("1234567890-x"));
+        assert_eq!(TrackingNumber::try_from("123456789x").err(), Some(TrackingNumberError::UnrecognizedFormat));
+    }
+
+    #[test]
+    fn tracking_number_rejects_leading_junk() {
+        assert!(is_valid_tracking_number("12345678901234"));
+        assert!(!is_valid_tracking_number("!12345678901234"));
+        assert!(!is_valid_tracking_number("ab-12345678901234"));
+        assert_eq!(TrackingNumber::try_from("#12345678901234").err(), Some(TrackingNumberError::UnrecognizedFormat));
+    }
+
+    #[test]
+    fn parse_dhl_datetime_handles_offsets() {
+        let expected = Utc.with_ymd_and_hms(2023, 1, 5, 13, 22, 0).unwrap();
+
+        assert_eq!(parse_dhl_datetime("2023-01-05T13:22:00Z"), Some(expected));
+        assert_eq!(parse_dhl_datetime("2023-01-05T14:22:00+01:00"), Some(expected));
+        assert_eq!(parse_dhl_datetime("2023-01-05T13:22:00"), Some(expected));
+        assert_eq!(parse_dhl_datetime("2023-01-05 13:22"), None);
+    }
+
+    #[test]
+    fn parse_dhl_datetime_handles_fractional_seconds() {
+        let expected = Utc.with_ymd_and_hms(2023, 1, 5, 14, 22, 0).unwrap() + chrono::Duration::milliseconds(123);
+
+        assert_eq!(parse_dhl_datetime("2023-01-05T14:22:00.123"), Some(expected));
+        let event: ShipmentEvent = serde_json::from_str(r#"{"timestamp":"2023-01-05T14:22:00.123"}"#).unwrap();
+        assert_eq!(event.timestamp, expected);
+    }
+
+    #[test]
+    fn possible_additional_shipments_url_can_be_missing_or_null() {
+        assert!(parse_response(&response_json("")).unwrap().possible_additional_shipments_url.is_empty());
+
+        let json = r#"{"shipments":[],"possibleAdditionalShipmentsUrl":null}"#;
+        assert!(parse_response(json).unwrap().possible_additional_shipments_url.is_empty());
+    }
+
+    fn parse_event(json: &str) -> ShipmentEvent {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn null_status_code_is_none() {
+        let event = parse_event(r#"{"timestamp":"2023-01-05T13:22:00","statusCode":null}"#);
+
+        assert_eq!(event.status_code, None);
+        assert_eq!(event.raw_status, None);
+    }
+
+    #[test]
+    fn status_code_can_be_a_string() {
+        let event = parse_event(r#"{"timestamp":"2023-01-05T13:22:00","statusCode":"delivered"}"#);
+
+        assert_eq!(event.status_code, Some(StatusCode::Delivered));
+    }
+
+    #[test]
+    fn status_code_can_be_nested_in_an_object() {
+        let event = parse_event(r#"{"timestamp":"2023-01-05T13:22:00","statusCode":{"statusCode":"delivered","status":"DELIVERED"}}"#);
+
+        assert_eq!(event.status_code, Some(StatusCode::Delivered));
+    }
+
+    #[test]
+    fn bare_shipments_array_parses() {
+        let json = response_json("");
+        let array = &json[r#"{"shipments":"#.len()..json.len() - 1];
+
+        assert_eq!(parse_response(array).unwrap().shipments[0].id, "1");
+        assert_eq!(parse_response_tolerant(array).unwrap().response.shipments.len(), 1);
+    }
+
+    #[test]
+    fn raw_status_is_only_kept_for_unknown_codes() {
+        let unknown = parse_event(r#"{"timestamp":"2023-01-05T13:22:00","statusCode":"held"}"#);
+        assert_eq!(unknown.status_code, Some(StatusCode::Unknown));
+        assert_eq!(unknown.raw_status.as_deref(), Some("held"));
+
+        let known = parse_event(r#"{"timestamp":"2023-01-05T13:22:00","statusCode":"delivered","rawStatus":"bogus"}"#);
+        assert_eq!(known.status_code, Some(StatusCode::Delivered));
+        assert_eq!(known.raw_status, None);
+
+        let serialized = parse_event(r#"{"timestamp":"2023-01-05T13:22:00","statusCode":"unknown","rawStatus":"held"}"#);
+        assert_eq!(serialized.raw_status.as_deref(), Some("held"));
+    }
+
+    #[test]
+    fn minimal_details_parse() {
+        let details = parse_shipment("").unwrap().details;
+
+        assert!(details.proof_of_delivery.is_none());
+        assert_eq!(details.total_number_of_pieces, 0);
+        assert!(details.piece_ids.is_empty());
+    }
+
+    #[test]
+    fn product_with_only_a_name_parses() {
+        let json = response_json("").replace(r#""details":{}"#, r#""details":{"product":{"productName":"DHL PAKET"}}"#);
+        let product = parse_response(&json).unwrap().shipments[0].details.product.clone().unwrap();
+
+        assert_eq!(product.product_name.as_deref(), Some("DHL PAKET"));
+        assert_eq!(product.description, None);
+    }
+
+    #[test]
+    fn estimated_time_of_delivery_can_be_missing_or_null() {
+        assert_eq!(parse_shipment("").unwrap().estimated_time_of_delivery, None);
+        assert_eq!(parse_shipment(r#","estimatedTimeOfDelivery":null"#).unwrap().estimated_time_of_delivery, None);
+
+        let shipment = parse_shipment(r#","estimatedTimeOfDelivery":"2023-01-06T10:00:00Z""#).unwrap();
+        assert_eq!(shipment.estimated_time_of_delivery, Some(DeliveryEstimate::Exact(Utc.with_ymd_and_hms(2023, 1, 6, 10, 0, 0).unwrap())));
+    }
+
+    #[cfg(feature = "surf-backend")]
+    #[test]
+    fn requests_reuse_the_connection() {
+        use std::io::{Read, Write};
+        use std::net::TcpListener;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}/track/shipments", listener.local_addr().unwrap());
+        let connections = Arc::new(AtomicUsize::new(0));
+
+        let accepted = connections.clone();
+        std::thread::spawn(move || for stream in listener.incoming() {
+            accepted.fetch_add(1, Ordering::SeqCst);
+            let mut stream = stream.unwrap();
+            std::thread::spawn(move || {
+                let body = response_json("");
+                let mut request = [0; 4096];
+                while matches!(stream.read(&mut request), Ok(read) if read > 0) {
+                    write!(stream, "HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n{}", body.len(), body).unwrap();
+                }
+            });
+        });
+
+        let client = Client::with_base_url("key", &base_url).unwrap();
+        for _ in 0..2 {
+            futures::executor::block_on(client.get_shipments(TrackingNumber::try_from("1234567890").unwrap())).unwrap();
+        }
+
+        assert_eq!(connections.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn base_url_with_a_query_or_fragment_is_rejected() {
+        assert!(Client::with_base_url("key", "https://x.example/track").is_ok());
+        assert!(matches!(Client::with_base_url("key", "https://x.example/track?foo=1"), Err(InvalidBaseUrl)));
+        assert!(matches!(Client::with_base_url("key", "https://x.example/track#top"), Err(InvalidBaseUrl)));
+    }
+
+    #[test]
+    fn reserved_characters_are_percent_encoded() {
+        // Built directly, validation doesn't accept these characters
+        let tracking_number = TrackingNumber { tracking_number: "GM/123+45&6".to_string() };
+
+        assert_eq!(
+            Client::new("key").shipments_uri(&tracking_number),
+            "https://api-eu.dhl.com/track/shipments?trackingNumber=GM%2F123%2B45%266",
+        );
+    }
+
+    #[test]
+    fn client_is_send_and_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+
+        assert_send_sync::<Client>();
+    }
+
+    #[test]
+    fn proof_of_delivery_only_gets_the_key_on_the_api_host() {
+        let backend = MockBackend::new(200, Vec::new(), b"%PDF".to_vec());
+        let client = Client::builder().api_key("key").backend(backend.clone()).build().unwrap();
+        let download = |document_url: &str| {
+            let proof_of_delivery = ProofOfDelivery { document_url: document_url.to_string(), signed_by: None, signature_url: None };
+            futures::executor::block_on(client.download_proof_of_delivery(&proof_of_delivery)).unwrap()
+        };
+
+        assert_eq!(download("https://api-eu.dhl.com/track/pod/1.pdf"), b"%PDF");
+        assert_eq!(backend.sent_header(0, API_KEY_HEADER).as_deref(), Some("key"));
+
+        download("https://gateway.example.com/track/pod/1.pdf");
+        assert_eq!(backend.sent_header(1, API_KEY_HEADER), None);
+    }
+
+    #[test]
+    fn serialized_response_parses_again() {
+        let json = r#"{"shipments":[
+            {"id":"1","service":"express","status":{"timestamp":"2023-01-05T13:22:00","statusCode":"transit"},"details":{"pieceIds":["JD1"]},
+                "events":[{"timestamp":"2023-01-05T12:00:00+01:00","statusCode":"held"}],"lastUpdated":"2023-01-05T13:30:00Z"},
+            {"id":"2","service":"parcel-de","status":{"timestamp":"2023-01-05T13:22:00"},"estimatedTimeOfDelivery":"2023-01-06T10:00:00Z","details":{},"events":[]},
+            {"id":"3","service":"dgf","status":{"timestamp":"2023-01-05T13:22:00"},"estimatedTimeOfDelivery":"2023-01-07","details":{},"events":[]},
+            {"id":"4","service":"freight","status":{"timestamp":"2023-01-05T13:22:00"},"details":{},"events":[],
+                "estimatedTimeOfDelivery":{"estimatedFrom":"2023-01-06T08:00:00Z","estimatedThrough":"2023-01-06T12:00:00Z"}}
+        ]}"#;
+        let response = parse_response(json).unwrap();
+
+        let parsed = parse_response(&serde_json::to_string(&response).unwrap()).unwrap();
+
+        assert_eq!(serde_json::to_value(&parsed).unwrap(), serde_json::to_value(&response).unwrap());
+        let estimates: Vec<Option<DeliveryEstimate>> = parsed.shipments.into_iter().map(|shipment| shipment.estimated_time_of_delivery).collect();
+        assert_eq!(estimates, vec![
+            None,
+            Some(DeliveryEstimate::Exact(Utc.with_ymd_and_hms(2023, 1, 6, 10, 0, 0).unwrap())),
+            Some(DeliveryEstimate::Window { from: Utc.with_ymd_and_hms(2023, 1, 7, 0, 0, 0).unwrap(), to: Utc.with_ymd_and_hms(2023, 1, 8, 0, 0, 0).unwrap() }),
+            Some(DeliveryEstimate::Window { from: Utc.with_ymd_and_hms(2023, 1, 6, 8, 0, 0).unwrap(), to: Utc.with_ymd_and_hms(2023, 1, 6, 12, 0, 0).unwrap() }),
+        ]);
+    }
+
+    #[cfg(feature = "compression")]
+    #[test]
+    fn gzip_response_is_decoded() {
+        use std::io::Write;
+
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(response_json("").as_bytes()).unwrap();
+        let backend = MockBackend::new(200, vec![("Content-Encoding".to_string(), "gzip".to_string())], encoder.finish().unwrap());
+        let client = Client::builder().api_key("key").backend(backend.clone()).build().unwrap();
+
+        let response = futures::executor::block_on(client.get_shipments(TrackingNumber::try_from("1234567890").unwrap())).unwrap();
+
+        assert_eq!(response.shipments[0].id, "1");
+        assert_eq!(backend.sent_header(0, "Accept-Encoding").as_deref(), Some("gzip, deflate"));
+    }
+
+    #[test]
+    fn not_found_keeps_the_api_error() {
+        let body = br#"{"status":404,"title":"No result found","detail":"No shipment with given tracking number found."}"#;
+        let client = Client::builder().api_key("key").backend(MockBackend::new(404, Vec::new(), body.to_vec())).build().unwrap();
+
+        match futures::executor::block_on(client.get_shipments(TrackingNumber::try_from("1234567890").unwrap())) {
+            Err(ParcelNotFound { tracking_number, api_error: Some(api_error) }) => {
+                assert_eq!(tracking_number, "1234567890");
+                assert_eq!(api_error.title.as_deref(), Some("No result found"));
+            },
+            other => panic!("Expected ParcelNotFound with an API error, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn long_retry_after_is_returned_instead_of_waited() {
+        let backend = MockBackend::new(429, vec![("Retry-After".to_string(), "86400".to_string())], Vec::new());
+        let client = Client::builder().api_key("key").backend(backend.clone()).max_retries(3).build().unwrap();
+
+        match futures::executor::block_on(client.get_shipments(TrackingNumber::try_from("1234567890").unwrap())) {
+            Err(RateLimited { retry_after }) => assert_eq!(retry_after, Some(Duration::from_secs(86400))),
+            other => panic!("Expected RateLimited, got {:?}", other.map(|_| ())),
+        }
+        assert_eq!(backend.requests.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn build_rejects_invalid_headers() {
+        let build = |name: &str, value: &str| Client::builder().api_key("key").header(name, value).build().map(|_| ());
+
+        assert!(build("X-Proxy-Token", "abc 123").is_ok());
+        assert!(matches!(build("X Proxy", "abc"), Err(InvalidHeader { ref name }) if name == "X Proxy"));
+        assert!(matches!(build("X-Proxy-Token", "abc\r\nHost: evil"), Err(InvalidHeader { .. })));
+        assert!(matches!(Client::builder().api_key("key").user_agent("bot\n").build(), Err(InvalidHeader { .. })));
+    }
+
+    // A response pointing the remaining pages at another host, as a rewriting gateway could
+    fn foreign_pages_backend() -> MockBackend {
+        let json = response_json("");
+        let body = format!(r#"{},"possibleAdditionalShipmentsUrl":["https://evil.example/steal?x=1"]}}"#, &json[..json.len() - 1]);
+        MockBackend::new(200, Vec::new(), body.into_bytes())
+    }
+
+    #[test]
+    fn malformed_additional_url_is_an_invalid_response() {
+        let client = Client::new("key");
+
+        assert!(matches!(client.resolve_url("http://[::1"), Err(InvalidResponse { .. })));
+        assert_eq!(client.resolve_url("/shipments?offset=5").unwrap(), "https://api-eu.dhl.com/track/shipments?offset=5");
+    }
+
+    #[test]
+    fn additional_pages_on_another_host_are_not_fetched() {
+        let backend = foreign_pages_backend();
+        let client = Client::builder().api_key("key").backend(backend.clone()).build().unwrap();
+
+        let result = futures::executor::block_on(client.get_all_shipments(TrackingNumber::try_from("1234567890").unwrap()));
+
+        assert!(matches!(result, Err(InvalidResponse { .. })));
+        let requests = backend.requests.lock().unwrap();
+        assert_eq!(requests.len(), 1);
+        assert!(requests.iter().all(|request| !request.url.contains("evil.example")));
+    }
+
+    #[test]
+    fn shipments_stream_skips_pages_on_another_host() {
+        let backend = foreign_pages_backend();
+        let client = Client::builder().api_key("key").backend(backend.clone()).build().unwrap();
+
+        let results: Vec<ClientResult<Shipment>> = futures::executor::block_on(
+            client.shipments_stream(TrackingNumber::try_from("1234567890").unwrap()).collect()
+        );
+
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].as_ref().unwrap().id, "1");
+        assert!(matches!(results[1], Err(InvalidResponse { .. })));
+        let requests = backend.requests.lock().unwrap();
+        assert_eq!(requests.len(), 1);
+        assert!(requests.iter().all(|request| !request.url.contains("evil.example")));
+    }
+
+    #[test]
+    fn set_user_agent_rejects_invalid_values() {
+        let mut client = Client::new("key");
 
-        //println!("Response: {:?}", &response.body_string().await?);
-        let res: Response = response.body_json().await?;
-        Ok(res)
+        assert!(client.set_user_agent("my-app/1.0").is_ok());
+        assert!(matches!(client.set_user_agent("bad\nHost: x"), Err(InvalidHeader { .. })));
+        assert_eq!(client.user_agent, "my-app/1.0");
     }
 }
@@ -0,0 +1,58 @@
+use crate::backend::{HttpBackend, HttpRequest, HttpResponse};
+use crate::errors::ClientError::*;
+use crate::errors::ClientResult;
+
+use futures::future::BoxFuture;
+
+// Needs to run within a tokio runtime, like any reqwest based code
+#[derive(Debug, Clone)]
+pub struct ReqwestBackend {
+    client: reqwest::Client,
+}
+
+impl Default for ReqwestBackend {
+    fn default() -> Self {
+        ReqwestBackend::new()
+    }
+}
+
+impl ReqwestBackend {
+    // Redirects are not followed, as with surf-backend: reqwest would forward
+    // the API key header to whichever host the redirect points at
+    pub fn new() -> ReqwestBackend {
+        let client = reqwest::Client::builder()
+            .redirect(reqwest::redirect::Policy::none())
+            .build()
+            .expect("Could not initialize the HTTP client");
+
+        ReqwestBackend { client }
+    }
+}
+
+impl HttpBackend for ReqwestBackend {
+    fn send(&self, request: HttpRequest) -> BoxFuture<'_, ClientResult<HttpResponse>> {
+        Box::pin(async move {
+            let mut builder = self.client.get(&request.url);
+            for (name, value) in &request.headers {
+                builder = builder.header(name.as_str(), value.as_str());
+            }
+
+            let mut response = builder.send().await?;
+
+            let status = response.status().as_u16();
+            let headers = response.headers().iter()
+                .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+                .collect();
+
+            let mut body = Vec::new();
+            while let Some(chunk) = response.chunk().await? {
+                if body.len() + chunk.len() > request.max_body_bytes {
+                    return Err(ResponseTooLarge { limit: request.max_body_bytes })
+                }
+                body.extend_from_slice(&chunk);
+            }
+
+            Ok(HttpResponse { status, headers, body })
+        })
+    }
+}
@@ -0,0 +1,116 @@
+use crate::backend::{HttpBackend, HttpRequest, HttpResponse};
+use crate::errors::ClientError::*;
+use crate::errors::ClientResult;
+
+use futures::future::BoxFuture;
+use std::sync::Arc;
+use surf::http;
+use surf::http::header::{HeaderName, HeaderValue};
+use surf::middleware::{Body, HttpClient, Middleware, Next, Request, Response};
+use surf_futures::io::AsyncReadExt;
+use url::Url;
+
+#[derive(Debug, Default)]
+pub struct SurfBackend {
+    client: surf::Client<IsahcClient>,
+}
+
+impl SurfBackend {
+    pub fn new() -> SurfBackend {
+        SurfBackend { client: surf::Client::with_client(IsahcClient::new()) }
+    }
+}
+
+impl HttpBackend for SurfBackend {
+    fn send(&self, request: HttpRequest) -> BoxFuture<'_, ClientResult<HttpResponse>> {
+        Box::pin(async move {
+            // surf panics on malformed URLs
+            let url = Url::parse(&request.url)
+                .map_err(|err| Transport { message: format!("Invalid request URL {}: {}", request.url, err) })?;
+
+            let headers = request.headers.iter()
+                .map(|(name, value)| Ok((
+                    HeaderName::from_bytes(name.as_bytes()).map_err(|_| InvalidHeader { name: name.clone() })?,
+                    HeaderValue::from_str(value).map_err(|_| InvalidHeader { name: name.clone() })?,
+                )))
+                .collect::<ClientResult<Vec<_>>>()?;
+
+            let mut response = self.client.get(url)
+                .middleware(RequestHeaders(headers))
+                .await?;
+
+            let status = response.status().as_u16();
+            let headers: Vec<(String, String)> = response.headers().iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect();
+
+            // Content-Length lets an oversized body be refused before reading any of it,
+            // chunked ones are read one byte past the limit at most
+            let too_large = |length: usize| length > request.max_body_bytes;
+            let content_length = headers.iter()
+                .find(|(name, _)| name.eq_ignore_ascii_case("Content-Length"))
+                .and_then(|(_, value)| value.trim().parse().ok());
+            if content_length.is_some_and(too_large) { return Err(ResponseTooLarge { limit: request.max_body_bytes }) }
+
+            let mut body = Vec::new();
+            (&mut response).take(request.max_body_bytes as u64 + 1).read_to_end(&mut body).await?;
+            if too_large(body.len()) { return Err(ResponseTooLarge { limit: request.max_body_bytes }) }
+
+            Ok(HttpResponse { status, headers, body })
+        })
+    }
+}
+
+// surf only takes static header names, this sets arbitrary ones on the way out
+struct RequestHeaders(Vec<(HeaderName, HeaderValue)>);
+
+impl<C: HttpClient> Middleware<C> for RequestHeaders {
+    fn handle<'a>(&'a self, mut req: Request, client: C, next: Next<'a, C>) -> BoxFuture<'a, Result<Response, surf::Exception>> {
+        for (name, value) in &self.0 {
+            req.headers_mut().insert(name.clone(), value.clone());
+        }
+        next.run(req, client)
+    }
+}
+
+// surf keeps its curl based client private, so this is the same thin wrapper
+// around isahc, which lets SurfBackend name it and keep one connection pool around
+#[derive(Debug, Clone)]
+struct IsahcClient {
+    client: Arc<isahc::HttpClient>,
+}
+
+impl Default for IsahcClient {
+    fn default() -> Self {
+        IsahcClient::new()
+    }
+}
+
+impl IsahcClient {
+    fn new() -> IsahcClient {
+        IsahcClient { client: Arc::new(isahc::HttpClient::new().expect("Could not initialize the HTTP client")) }
+    }
+}
+
+impl HttpClient for IsahcClient {
+    type Error = isahc::Error;
+
+    fn send(&self, req: Request) -> BoxFuture<'static, Result<Response, Self::Error>> {
+        let client = self.client.clone();
+        Box::pin(async move {
+            let (parts, body) = req.into_parts();
+            // surf always hands over a reader of unknown length, which curl would otherwise
+            // send chunked with an Expect header, and drop the connection afterwards
+            let body = match parts.method {
+                http::Method::GET | http::Method::HEAD => isahc::Body::empty(),
+                _ => isahc::Body::reader(body),
+            };
+            let req = http::Request::from_parts(parts, body);
+
+            let res = client.send_async(req).await?;
+
+            let (parts, body) = res.into_parts();
+            Ok(http::Response::from_parts(parts, Body::from_reader(body)))
+        })
+    }
+}